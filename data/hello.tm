@@ -11,5 +11,5 @@ q8	-	-	-	-	-	-	-	-	-	q9,R,R
 q9	-	-	-	-	-	-	-	-	-	qA,L,R
 qA	-	-	-	-	-	-	-	-	-	qB,D,R
 qB	-	-	-	-	-	-	-	-	-	q←,!,L
-q←	q←,H,L	q←,E,L	q←,L,L	q←,O,L	q←,.,L	q←,W,L	q←,R,L	q←,D,L	q←,!,L	STOPP,B,R
+q←	*:=,=,L	B:STOPP,B,R
 STOPP
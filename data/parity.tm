@@ -0,0 +1,6 @@
+# Accepts inputs with an even number of 1s.
+	1	B
+even	odd,1,R	ACCEPT,B,N
+odd	even,1,R	REJECT,B,N
+!accept	ACCEPT
+!reject	REJECT
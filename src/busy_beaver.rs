@@ -0,0 +1,206 @@
+//! The compact notation that the busy beaver community uses to share
+//! machines, like `1RB1LB_1LA0LZ`.
+//!
+//! States are separated by `_`, and are called `A`, `B`, ... in order, with
+//! `A` as the initial state. Each state has one transition per symbol, and the
+//! symbols are the digits `0`, `1`, ..., where `0` is the blank. A transition
+//! is the symbol to write, the movement (`L` or `R`) and the next state, like
+//! `1RB`. `---` is an undefined transition. A next state of `Z` (or any other
+//! letter that isn't a state) or `-` means that the machine halts after the
+//! transition.
+//!
+//! In a `TMDesc`, the blank `0` is the last symbol, and the machine halts by
+//! going to a final state called `Z` (or the letter that was used).
+
+use {TMDesc, TMDescError, ExportError, State, Transition, Movement, HaltKind, Format};
+
+/// The name of the final state, when it isn't given.
+static HALT: &'static str = "Z";
+
+/// States are named with capital letters, and one has to be left for the
+/// final state.
+const MAX_STATES: usize = 25;
+
+const MAX_SYMBOLS: usize = 10;
+
+fn state_name(index: usize) -> String {
+    ((b'A' + index as u8) as char).to_string()
+}
+
+fn digit(index: usize) -> char {
+    (b'0' + index as u8) as char
+}
+
+impl TMDesc {
+    /// Read a machine in the busy beaver notation. Surrounding whitespace is
+    /// ignored.
+    pub fn from_busy_beaver(s: &str) -> Result<TMDesc, TMDescError> {
+        let mut lines = s.lines().enumerate().filter(|&(_, l)| !l.trim().is_empty());
+        let (line, text) = match lines.next() {
+            Some((i, text)) => (i + 1, text),
+            None => return Err(TMDescError::Malformed {
+                format: Format::BusyBeaver, line: 0, column: 0,
+                message: "there is no machine".into()
+            })
+        };
+        if let Some((i, _)) = lines.next() {
+            return Err(TMDescError::Malformed {
+                format: Format::BusyBeaver, line: i + 1, column: 1,
+                message: "there is more than one machine".into()
+            });
+        }
+        let error = |column: usize, message: String| TMDescError::Malformed {
+            format: Format::BusyBeaver, line: line, column: column, message: message
+        };
+
+        let indent = text.chars().take_while(|c| c.is_whitespace()).count();
+        let rows = text.trim().split('_').collect::<Vec<_>>();
+        let num_symbols = rows[0].chars().count() / 3;
+        if rows.len() > MAX_STATES {
+            return Err(error(1 + indent, format!("there are more than {} states", MAX_STATES)));
+        }
+        if num_symbols < 2 || num_symbols > MAX_SYMBOLS {
+            return Err(error(1 + indent, format!("there must be 2 to {} symbols", MAX_SYMBOLS)));
+        }
+
+        let mut desc = TMDesc::new();
+        // The blank goes last.
+        desc.input_symbols = (1..num_symbols).map(digit).collect();
+        desc.input_symbols.push('0');
+
+        let mut column = 1 + indent;
+        for (i, row) in rows.iter().enumerate() {
+            let chars = row.chars().collect::<Vec<_>>();
+            if chars.len() != 3 * num_symbols {
+                return Err(error(column, format!("state {} has {} characters, but should \
+                                                 have {}", state_name(i), chars.len(),
+                                                 3 * num_symbols)));
+            }
+
+            let mut state = State::empty(state_name(i), num_symbols);
+            for (read, t) in chars.chunks(3).enumerate() {
+                let text = t.iter().cloned().collect::<String>();
+                // The blank is the last symbol in the `TMDesc`.
+                let index = if read == 0 { num_symbols - 1 } else { read - 1 };
+                let column = column + 3 * read;
+                if text == "---" {
+                    continue;
+                }
+
+                if !t[0].is_digit(10) || t[0] as usize - '0' as usize >= num_symbols {
+                    return Err(error(column, format!("`{}' is not a symbol", t[0])));
+                }
+                let movement = match t[1] {
+                    'L' => Movement::Left,
+                    'R' => Movement::Right,
+                    c => return Err(error(column + 1, format!("`{}' is not a valid movement \
+                                                              (L or R)", c)))
+                };
+                let next = match t[2] {
+                    '-' => HALT.into(),
+                    c if c >= 'A' && c <= 'Z' => c.to_string(),
+                    c => return Err(error(column + 2, format!("`{}' is not a state", c)))
+                };
+                state.transitions[index] = Some(Transition {
+                    state: next,
+                    state_index: None,
+                    symbol: t[0],
+                    movement: movement
+                });
+            }
+            desc.states.push(state);
+            column += chars.len() + 1;
+        }
+
+        // Letters that aren't states are final states, in the order in which
+        // they are used.
+        let mut finals = vec![];
+        for state in desc.states.iter() {
+            for trans in state.transitions.iter().filter_map(|t| t.as_ref()) {
+                if (trans.state.as_bytes()[0] - b'A') as usize >= rows.len() &&
+                        !finals.contains(&trans.state) {
+                    finals.push(trans.state.clone());
+                }
+            }
+        }
+        for name in finals {
+            let mut state = State::empty(name, num_symbols);
+            state.halt = Some(HaltKind::Halt);
+            desc.states.push(state);
+        }
+
+        Ok(desc)
+    }
+
+    /// Write the machine in the busy beaver notation. The blank becomes `0`,
+    /// and the other symbols become `1`, `2`, ... in order. Transitions to
+    /// final states go to `Z`, and the transitions of final states are left
+    /// out.
+    ///
+    /// This fails for machines with too many states or symbols, or with `N`
+    /// movements, which the notation lacks.
+    pub fn to_busy_beaver(&self) -> Result<String, ExportError> {
+        let error = |reason: String| ExportError {
+            format: Format::BusyBeaver, reason: reason
+        };
+
+        let states = self.states.iter().enumerate().filter(|&(_, s)| !s.is_final())
+            .map(|(i, _)| i).collect::<Vec<_>>();
+        if states.len() > MAX_STATES {
+            return Err(error(format!("there are more than {} states", MAX_STATES)));
+        }
+        if states.first() != Some(&0) {
+            return Err(error("the initial state is final".into()));
+        }
+        let n = self.input_symbols.len();
+        if n > MAX_SYMBOLS {
+            return Err(error(format!("there are more than {} symbols", MAX_SYMBOLS)));
+        }
+
+        // The digit of each symbol, with the blank as 0
+        let number = |symbol: char| {
+            self.input_symbols.iter().position(|&c| c == symbol).map(|i| {
+                if i == n - 1 { 0 } else { i + 1 }
+            })
+        };
+        // The input symbols in the order of their digits
+        let order = (0..n).map(|d| if d == 0 { n - 1 } else { d - 1 }).collect::<Vec<_>>();
+
+        let mut rows = vec![];
+        for &i in states.iter() {
+            let state = &self.states[i];
+            let mut row = String::new();
+            for &read in order.iter() {
+                let trans = match state.transitions[read] {
+                    Some(ref trans) => trans,
+                    None => {
+                        row.push_str("---");
+                        continue;
+                    }
+                };
+
+                let symbol = try!(number(trans.symbol).ok_or_else(|| {
+                    error(format!("`{}' is not an input symbol", trans.symbol))
+                }));
+                let movement = match trans.movement {
+                    Movement::Left => 'L',
+                    Movement::Right => 'R',
+                    Movement::None => return Err(error(format!(
+                        "state {} doesn't move on `{}'", state.name, self.input_symbols[read])))
+                };
+                let target = try!(self.find_state(&trans.state).ok_or_else(|| {
+                    error(format!("state `{}' is not defined", trans.state))
+                }));
+                let next = match states.iter().position(|&s| s == target) {
+                    Some(index) => state_name(index),
+                    None => HALT.into()
+                };
+                row.push(digit(symbol));
+                row.push(movement);
+                row.push_str(&next);
+            }
+            rows.push(row);
+        }
+        Ok(rows.join("_"))
+    }
+}
@@ -7,15 +7,77 @@ extern crate libc;
 extern crate turing_machines;
 extern crate getopts;
 
-use turing_machines::{TMDesc, Movement};
+use turing_machines::{TMDesc, State, Movement, HaltKind, Format};
 use std::ffi::CString;
-use std::process::exit;
-use std::path::Path;
+use std::process::{exit, Command};
+use std::path::{Path, PathBuf};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+
+/// libturingrt, which build.rs leaves in OUT_DIR. It's part of the binary, so
+/// that turingc keeps working when the build directory is gone.
+static RUNTIME: &'static [u8] = include_bytes!(concat!(env!("OUT_DIR"), "/libturingrt.a"));
 
 mod wrapper {
     use rustc_llvm as llvm;
-    use std::ffi::CString;
-    use libc::{c_uint, c_ulonglong};
+    use std::ffi::{CString, CStr};
+    use std::path::Path;
+    use std::ptr;
+    use std::os::unix::ffi::OsStrExt;
+    use libc::{c_char, c_uint, c_ulonglong};
+
+    // Parts of the LLVM C API that rustc_llvm doesn't declare.
+    #[allow(non_snake_case)]
+    #[allow(non_camel_case_types)]
+    mod ffi {
+        use rustc_llvm as llvm;
+        use libc::{c_char, c_uint};
+
+        pub enum Target_opaque {}
+        pub type TargetRef = *mut Target_opaque;
+
+        // LLVMCodeGenOptLevel, LLVMRelocMode, LLVMCodeModel and
+        // LLVMCodeGenFileType values
+        pub const CODEGEN_LEVEL_DEFAULT: c_uint = 2;
+        pub const RELOC_PIC: c_uint = 2;
+        pub const CODE_MODEL_DEFAULT: c_uint = 0;
+        pub const OBJECT_FILE: c_uint = 1;
+
+        extern {
+            pub fn LLVMPrintModuleToFile(M: llvm::ModuleRef, Filename: *const c_char,
+                                         ErrorMessage: *mut *mut c_char) -> c_uint;
+            pub fn LLVMWriteBitcodeToFile(M: llvm::ModuleRef, Path: *const c_char) -> c_uint;
+            pub fn LLVMSetTarget(M: llvm::ModuleRef, Triple: *const c_char);
+            pub fn LLVMGetDefaultTargetTriple() -> *mut c_char;
+            pub fn LLVMGetTargetFromTriple(Triple: *const c_char, T: *mut TargetRef,
+                                           ErrorMessage: *mut *mut c_char) -> c_uint;
+            pub fn LLVMCreateTargetMachine(T: TargetRef, Triple: *const c_char,
+                                           CPU: *const c_char, Features: *const c_char,
+                                           Level: c_uint, Reloc: c_uint,
+                                           CodeModel: c_uint) -> llvm::TargetMachineRef;
+            pub fn LLVMTargetMachineEmitToFile(T: llvm::TargetMachineRef, M: llvm::ModuleRef,
+                                               Filename: *const c_char, codegen: c_uint,
+                                               ErrorMessage: *mut *mut c_char) -> c_uint;
+            pub fn LLVMDisposeTargetMachine(T: llvm::TargetMachineRef);
+            pub fn LLVMDisposeMessage(Message: *mut c_char);
+        }
+    }
+
+    fn path_to_cstring(path: &Path) -> CString {
+        CString::new(path.as_os_str().as_bytes()).unwrap()
+    }
+
+    /// Turn an error message allocated by LLVM into a String, and free it.
+    unsafe fn take_message(message: *mut c_char) -> String {
+        if message.is_null() {
+            return "unknown error".into();
+        }
+        let string = CStr::from_ptr(message).to_string_lossy().into_owned();
+        ffi::LLVMDisposeMessage(message);
+        string
+    }
 
     pub struct Module<'a> {
         raw: llvm::ModuleRef,
@@ -61,6 +123,34 @@ mod wrapper {
                 llvm::LLVMAddGlobal(self.raw, ty.0, name.as_ptr())
             })
         }
+
+        pub fn set_target(&mut self, triple: &CString) {
+            unsafe {
+                ffi::LLVMSetTarget(self.raw, triple.as_ptr())
+            }
+        }
+
+        /// Write the module as textual IR.
+        pub fn write_ir(&self, path: &Path) -> Result<(), String> {
+            let path = path_to_cstring(path);
+            unsafe {
+                let mut message = ptr::null_mut();
+                if ffi::LLVMPrintModuleToFile(self.raw, path.as_ptr(), &mut message) != 0 {
+                    return Err(take_message(message));
+                }
+            }
+            Ok(())
+        }
+
+        pub fn write_bitcode(&self, path: &Path) -> Result<(), String> {
+            let path = path_to_cstring(path);
+            unsafe {
+                if ffi::LLVMWriteBitcodeToFile(self.raw, path.as_ptr()) != 0 {
+                    return Err("failed to write bitcode".into());
+                }
+            }
+            Ok(())
+        }
     }
 
     impl<'a> Drop for Module<'a> {
@@ -276,12 +366,73 @@ mod wrapper {
 
     #[derive(Clone, Copy)]
     struct BasicBlock(llvm::BasicBlockRef);
+
+    pub struct TargetMachine {
+        raw: llvm::TargetMachineRef,
+        triple: CString
+    }
+
+    impl TargetMachine {
+        /// A target machine for the host that turingc is running on.
+        pub fn host() -> Result<TargetMachine, String> {
+            llvm::initialize_available_targets();
+
+            unsafe {
+                let raw_triple = ffi::LLVMGetDefaultTargetTriple();
+                let triple = CStr::from_ptr(raw_triple).to_owned();
+                ffi::LLVMDisposeMessage(raw_triple);
+
+                let mut target = ptr::null_mut();
+                let mut message = ptr::null_mut();
+                if ffi::LLVMGetTargetFromTriple(triple.as_ptr(), &mut target, &mut message) != 0 {
+                    return Err(take_message(message));
+                }
+
+                let empty = CString::new("").unwrap();
+                let raw = ffi::LLVMCreateTargetMachine(
+                    target, triple.as_ptr(), empty.as_ptr(), empty.as_ptr(),
+                    ffi::CODEGEN_LEVEL_DEFAULT, ffi::RELOC_PIC, ffi::CODE_MODEL_DEFAULT
+                );
+                if raw.is_null() {
+                    return Err("failed to create a target machine".into());
+                }
+
+                Ok(TargetMachine {
+                    raw: raw,
+                    triple: triple
+                })
+            }
+        }
+
+        pub fn triple(&self) -> &CString {
+            &self.triple
+        }
+
+        /// Compile the module to an object file.
+        pub fn emit_object(&self, module: &Module, path: &Path) -> Result<(), String> {
+            let path = path_to_cstring(path);
+            unsafe {
+                let mut message = ptr::null_mut();
+                if ffi::LLVMTargetMachineEmitToFile(self.raw, module.raw, path.as_ptr(),
+                                                    ffi::OBJECT_FILE, &mut message) != 0 {
+                    return Err(take_message(message));
+                }
+            }
+            Ok(())
+        }
+    }
+
+    impl Drop for TargetMachine {
+        fn drop(&mut self) {
+            unsafe { ffi::LLVMDisposeTargetMachine(self.raw) }
+        }
+    }
 }
 
-fn build_module(tmdesc: &TMDesc, config: &Config) {
-    use wrapper::{Context, Module, Ty, Builder};
+fn build_module<'a>(tmdesc: &TMDesc, config: &Config,
+                    context: &'a wrapper::Context) -> wrapper::Module<'a> {
+    use wrapper::{Module, Ty, Builder};
 
-    let context = Context::new();
     let ty_void = context.void_type();
     let ty_i8 = context.int8_type();
     let ty_i32 = context.int32_type();
@@ -316,6 +467,16 @@ fn build_module(tmdesc: &TMDesc, config: &Config) {
         module.get_or_insert_function(&CString::new("tm_fail").unwrap(), ty)
     };
 
+    // const char *tm_halt_state; uint32_t tm_halt_kind;
+    // Both are defined in libturingrt, and tell it which final state was
+    // reached.
+    let tm_halt_state = module.add_global(ty_i8.pointer_type(0),
+                                          &CString::new("tm_halt_state").unwrap());
+    let tm_halt_kind = module.add_global(ty_i32, &CString::new("tm_halt_kind").unwrap());
+
+    // int tm_exit_status; -- also defined in libturingrt, and returned from main
+    let tm_exit_status = module.add_global(ty_int, &CString::new("tm_exit_status").unwrap());
+
     // build the turing machine function with signature u32 *tm(u32 *TP)
     let tm_func = {
         // Map each state to a basic block like this:
@@ -327,7 +488,8 @@ fn build_module(tmdesc: &TMDesc, config: &Config) {
         //   default: tm_fail("q2", *TP);
         // }
         //
-        // ... except for final states, which are encoded as "return TP".
+        // ... except for final states, which are encoded like this:
+        // tm_halt_state = "q9"; tm_halt_kind = 1; tm_exit_status = 0; return TP;
 
         let function = module.add_function(&CString::new("tm").unwrap(), ty_i32p_i32p);
         let mut builder = Builder::new(&context);
@@ -350,7 +512,13 @@ fn build_module(tmdesc: &TMDesc, config: &Config) {
 
             let tp = builder.build_load(tp_var, &empty);
 
-            if state.is_final() {
+            if let Some(kind) = state.halt {
+                let name = builder.build_global_string(&CString::new(&state.name[..]).unwrap(), &empty);
+                let name_ptr = builder.build_gep(name, zero_i32_twice, &empty);
+                builder.build_store(name_ptr, tm_halt_state);
+                builder.build_store(ty_i32.const_int(kind as u64), tm_halt_kind);
+                let status = exit_status(state, config);
+                builder.build_store(ty_int.const_int(status as u64), tm_exit_status);
                 builder.build_ret(tp);
                 continue;
             }
@@ -417,9 +585,10 @@ fn build_module(tmdesc: &TMDesc, config: &Config) {
         let tm_run_arg_types = &[
             ty_i32p_i32p_p, // the tm function
             ty_i32p,        // table of input symbols
-            ty_i32          // table size
+            ty_i32,         // table size
+            ty_int          // exit status for tm_fail
         ];
-        let tm_run_ty = Ty::function_type(ty_void, tm_run_arg_types, false);
+        let tm_run_ty = Ty::function_type(ty_int, tm_run_arg_types, false);
         let tm_run = module.get_or_insert_function(&CString::new("tm_run").unwrap(), tm_run_ty);
 
         let mut builder = Builder::new(&context);
@@ -429,36 +598,76 @@ fn build_module(tmdesc: &TMDesc, config: &Config) {
 
         let table_ptr = builder.build_gep(table, zero_i32_twice, &empty);
 
-        let tm_run_args = &[tm_func, table_ptr, table_size];
+        let fail_status = ty_int.const_int(config.fail_status as u64);
+        let tm_run_args = &[tm_func, table_ptr, table_size, fail_status];
 
-        builder.build_call(tm_run, tm_run_args, &empty);
-        builder.build_ret(ty_i32.const_int(0));
+        let status = builder.build_call(tm_run, tm_run_args, &empty);
+        builder.build_ret(status);
     }
 
-    module.dump();
+    module
+}
+
+/// The exit status of the compiled program when it stops in the given (final)
+/// state.
+fn exit_status(state: &State, config: &Config) -> i32 {
+    match config.exit_statuses.iter().find(|&&(ref name, _)| *name == state.name) {
+        Some(&(_, status)) => status,
+        None => match state.halt {
+            Some(HaltKind::Reject) => 1,
+            _ => 0
+        }
+    }
 }
 
 fn map_blank_to_null(desc: &TMDesc, sym: char) -> char {
     if sym == desc.blank_symbol() { '\0' } else { sym }
 }
 
-enum Emit {
+/// The kinds of output that are generated with LLVM.
+#[derive(Clone, Copy)]
+enum Code {
     Exe,
     TextIR,
     Bytecode,
     Object
 }
 
+enum Emit {
+    Code(Code),
+    /// A Graphviz graph of the states, instead of code
+    Dot,
+    /// The machine itself, in the given format
+    Machine(Format)
+}
+
 impl Emit {
-    fn parse(arg: Option<&str>) -> Emit {
-        use Emit::*;
+    /// The extension of output files, if none is given with `-o`.
+    fn extension(&self) -> &'static str {
+        match *self {
+            Emit::Code(Code::Exe) => "",
+            Emit::Code(Code::TextIR) => "ll",
+            Emit::Code(Code::Bytecode) => "bc",
+            Emit::Code(Code::Object) => "o",
+            Emit::Dot => "dot",
+            Emit::Machine(format) => format.extension()
+        }
+    }
 
+    fn parse(arg: Option<&str>) -> Emit {
         match arg {
-            None => Exe,
-            Some("exe") => Exe,
-            Some("ll") => TextIR,
-            Some("bc") => Bytecode,
-            Some("o") => Object,
+            None => Emit::Code(Code::Exe),
+            Some("exe") => Emit::Code(Code::Exe),
+            Some("ll") => Emit::Code(Code::TextIR),
+            Some("bc") => Emit::Code(Code::Bytecode),
+            Some("o") => Emit::Code(Code::Object),
+            Some("dot") => Emit::Dot,
+            Some("tm") => Emit::Machine(Format::Tm),
+            Some("json") => Emit::Machine(Format::Json),
+            Some("bb") => Emit::Machine(Format::BusyBeaver),
+            Some("yaml") => Emit::Machine(Format::Yaml),
+            Some("jff") => Emit::Machine(Format::Jflap),
+            Some("quint") => Emit::Machine(Format::Quintuple),
             Some(arg) => {
                 println!("Invalid argument {} to --emit\n", arg);
                 exit(1);
@@ -469,9 +678,24 @@ impl Emit {
 
 struct Config {
     input: String,
+    format: Format,
     output: Option<String>,
     emit: Emit,
     fail_is_unreachable: bool,
+    /// Exit statuses for specific final states
+    exit_statuses: Vec<(String, i32)>,
+    /// Exit status when there's no transition
+    fail_status: i32,
+}
+
+fn parse_status(arg: &str) -> i32 {
+    match arg.parse() {
+        Ok(status) if status >= 0 && status < 256 => status,
+        _ => {
+            println!("Invalid exit status {}\n", arg);
+            exit(1);
+        }
+    }
 }
 
 fn get_config() -> Config {
@@ -480,12 +704,29 @@ fn get_config() -> Config {
     let options = &[
         optflag("h", "help", "Print a help message"),
         optopt("o", "output", "The output file", "FILE"),
+        optopt("", "format",
+                "Read the machine in the TM format (tm), as JSON (json), in the \
+                busy beaver notation (bb), as a turingmachine.io document \
+                (yaml), as a JFLAP file (jff), or as quintuples (quint). By \
+                default, the format is taken from the file extension",
+                "tm|json|bb|yaml|jff|quint"),
         optopt("", "emit",
                 "Emit an executable (exe, default), textual LLVM IR (ll), \
-                bytecode (bc), or an object file (o)",
-                "exe|ll|bc|o"),
+                bytecode (bc), an object file (o), a Graphviz graph of the \
+                states (dot), or the machine itself as a TM file (tm), as \
+                JSON (json), in the busy beaver notation (bb), as a \
+                turingmachine.io document (yaml), as a JFLAP file (jff) or as \
+                quintuples (quint)",
+                "exe|ll|bc|o|dot|tm|json|bb|yaml|jff|quint"),
         optflag("", "fail-is-unreachable",
                 "Instead of emitting a call to tm_fail, assume that failures are unreachable."),
+        optmulti("", "exit-status",
+                "Exit with STATUS when the machine halts in STATE. By default, \
+                reject states exit with 1 and other final states with 0.",
+                "STATE=STATUS"),
+        optopt("", "fail-status",
+                "Exit with STATUS when there's no transition (default: 2)",
+                "STATUS"),
     ];
 
     let mut args = std::env::args();
@@ -508,17 +749,40 @@ fn get_config() -> Config {
 
     let emit = matches.opt_str("emit");
 
+    let format = matches.opt_str("format").map_or_else(|| {
+        Format::from_path(Path::new(&matches.free[0]))
+    }, |arg| {
+        Format::parse(&arg).unwrap_or_else(|| {
+            println!("Invalid argument {} to --format\n", arg);
+            exit(1);
+        })
+    });
+
+    let exit_statuses = matches.opt_strs("exit-status").iter().map(|arg| {
+        let mut parts = arg.splitn(2, '=');
+        match (parts.next(), parts.next()) {
+            (Some(state), Some(status)) => (state.to_string(), parse_status(status)),
+            _ => {
+                println!("Invalid argument {} to --exit-status\n", arg);
+                exit(1);
+            }
+        }
+    }).collect();
+
     Config {
         input: matches.free[0].clone(),
+        format: format,
         output: matches.opt_str("o"),
         emit: Emit::parse(emit.as_ref().map(|s|&s[..])),
         fail_is_unreachable: matches.opt_present("fail-is-unreachable"),
+        exit_statuses: exit_statuses,
+        fail_status: matches.opt_str("fail-status").map_or(2, |s| parse_status(&s)),
     }
 }
 
 fn main() {
     let config = get_config();
-    let desc = match TMDesc::from_file(&Path::new(&config.input)) {
+    let desc = match TMDesc::from_file_in(config.format, &Path::new(&config.input)) {
         Ok(desc) => desc,
         Err(e) => {
             println!("Failed to load `{}`. Reason: {}", config.input, e);
@@ -526,5 +790,118 @@ fn main() {
         }
     };
 
-    build_module(&desc, &config);
+    for &(ref name, _) in config.exit_statuses.iter() {
+        match desc.find_state(name) {
+            None => {
+                println!("--exit-status: there is no state called {}", name);
+                exit(1);
+            }
+            Some(index) if !desc.states[index].is_final() => {
+                println!("--exit-status: state {} is not a halting, accepting or \
+                          rejecting state", name);
+                exit(1);
+            }
+            Some(_) => ()
+        }
+    }
+
+    let output = match config.output {
+        Some(ref output) => PathBuf::from(output),
+        None => {
+            let stem = Path::new(&config.input).file_stem().unwrap_or("tm".as_ref());
+            let output = PathBuf::from(stem).with_extension(config.emit.extension());
+            // Don't overwrite an input file that has no extension.
+            if output == Path::new(&config.input) {
+                output.with_extension("out")
+            } else {
+                output
+            }
+        }
+    };
+
+    // Only code needs LLVM, the other outputs are just text.
+    let result = match config.emit {
+        Emit::Code(code) => {
+            let context = wrapper::Context::new();
+            let module = build_module(&desc, &config, &context);
+            emit(module, code, &output)
+        }
+        Emit::Dot => write_text(&desc.to_dot(), &output),
+        Emit::Machine(format) => match desc.to_string_in(format) {
+            Ok(text) => write_text(&text, &output),
+            Err(e) => {
+                println!("Failed to convert `{}`. Reason: {}", config.input, e);
+                exit(1);
+            }
+        }
+    };
+    if let Err(e) = result {
+        println!("Failed to write `{}`. Reason: {}", output.display(), e);
+        exit(1);
+    }
+}
+
+fn write_text(text: &str, output: &Path) -> Result<(), String> {
+    File::create(output).and_then(|mut file| file.write_all(text.as_bytes()))
+        .map_err(|e| e.to_string())
+}
+
+fn emit(mut module: wrapper::Module, code: Code, output: &Path) -> Result<(), String> {
+    match code {
+        Code::TextIR => module.write_ir(output),
+        Code::Bytecode => module.write_bitcode(output),
+        Code::Object | Code::Exe => {
+            let target = try!(wrapper::TargetMachine::host());
+            module.set_target(target.triple());
+
+            if let Code::Object = code {
+                return target.emit_object(&module, output);
+            }
+
+            let (object, _) = try!(temp_file("o"));
+            let result = temp_file("a").and_then(|(runtime, mut file)| {
+                let result = file.write_all(RUNTIME)
+                    .map_err(|e| format!("failed to write `{}`: {}", runtime.display(), e))
+                    .and_then(|_| target.emit_object(&module, &object))
+                    .and_then(|_| link(&object, &runtime, output));
+                let _ = fs::remove_file(&runtime);
+                result
+            });
+            let _ = fs::remove_file(&object);
+            result
+        }
+    }
+}
+
+/// Create an empty file in the temporary directory, with the given
+/// extension. The name is random, and the file must not exist yet, so that
+/// concurrent runs (and other users) can't get in the way.
+fn temp_file(extension: &str) -> Result<(PathBuf, File), String> {
+    for i in 0..100 {
+        let mut hasher = RandomState::new().build_hasher();
+        hasher.write_u32(i);
+        let mut path = std::env::temp_dir();
+        path.push(format!("turingc-{:016x}.{}", hasher.finish(), extension));
+        match OpenOptions::new().write(true).create_new(true).open(&path) {
+            Ok(file) => return Ok((path, file)),
+            Err(ref e) if e.kind() == io::ErrorKind::AlreadyExists => (),
+            Err(e) => return Err(format!("failed to create `{}`: {}", path.display(), e))
+        }
+    }
+    Err("failed to find a name for a temporary file".into())
+}
+
+/// Link an object file with the runtime library, using the system's C
+/// compiler.
+fn link(object: &Path, runtime: &Path, output: &Path) -> Result<(), String> {
+    let cc = std::env::var("CC").unwrap_or("cc".into());
+
+    let status = try!(Command::new(&cc).arg(object).arg(runtime)
+                      .arg("-o").arg(output).status()
+                      .map_err(|e| format!("failed to run {}: {}", cc, e)));
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("{} failed ({})", cc, status))
+    }
 }
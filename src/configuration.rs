@@ -0,0 +1,136 @@
+//! Snapshots of running machines, which can be saved and resumed later.
+//!
+//! A configuration is stored as a JSON object like this:
+//!
+//! ```text
+//! {"head":2,"state":"q1","steps":17,"tape":"1101B","tape_start":-1}
+//! ```
+
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::Path;
+use rustc_serialize::json::{Json, ToJson};
+use {TM, TMDesc, Tape, ConfigurationError};
+
+/// Everything that is needed to continue a run of a machine.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Configuration {
+    /// The name of the current state
+    pub state: String,
+    pub head: isize,
+    /// The used part of the tape
+    pub tape: String,
+    /// The position of the first symbol of `tape`
+    pub tape_start: isize,
+    /// The number of steps executed so far
+    pub steps: u64
+}
+
+impl Configuration {
+    pub fn new(state: &str, head: isize, tape: &Tape, steps: u64) -> Configuration {
+        Configuration {
+            state: state.into(),
+            head: head,
+            tape: tape.to_string(),
+            tape_start: tape.min(),
+            steps: steps
+        }
+    }
+
+    pub fn from_json(json: &Json) -> Result<Configuration, ConfigurationError> {
+        fn field<'a>(json: &'a Json, name: &'static str)
+                -> Result<&'a Json, ConfigurationError> {
+            json.find(name).ok_or(ConfigurationError::BadField(name))
+        }
+        fn int(json: &Json, name: &'static str) -> Result<isize, ConfigurationError> {
+            try!(field(json, name)).as_i64().map(|i| i as isize)
+                .ok_or(ConfigurationError::BadField(name))
+        }
+        fn string(json: &Json, name: &'static str) -> Result<String, ConfigurationError> {
+            try!(field(json, name)).as_string().map(|s| s.into())
+                .ok_or(ConfigurationError::BadField(name))
+        }
+
+        Ok(Configuration {
+            state: try!(string(json, "state")),
+            head: try!(int(json, "head")),
+            tape: try!(string(json, "tape")),
+            tape_start: try!(int(json, "tape_start")),
+            steps: try!(try!(field(json, "steps")).as_u64()
+                        .ok_or(ConfigurationError::BadField("steps")))
+        })
+    }
+
+    /// Check that the configuration fits the machine, and return the index
+    /// of its state. Like the head, position 0 has to be on the tape, or
+    /// right next to it, because the tape is always stored from there on.
+    /// Otherwise, a broken file could make the tape huge.
+    pub fn check(&self, desc: &TMDesc) -> Result<usize, ConfigurationError> {
+        try!(desc.check_runnable().map_err(ConfigurationError::Invalid));
+        let end = self.tape_start.saturating_add(self.tape.chars().count() as isize);
+        if self.tape_start > 1 || end < -1 {
+            return Err(ConfigurationError::TapeOutOfRange(self.tape_start));
+        }
+        if self.head < self.tape_start - 1 || self.head > end {
+            return Err(ConfigurationError::HeadOutOfRange(self.head));
+        }
+        desc.find_state(&self.state).ok_or_else(|| {
+            ConfigurationError::UnknownState(self.state.clone())
+        })
+    }
+
+    pub fn from_string(s: &str) -> Result<Configuration, ConfigurationError> {
+        Configuration::from_json(&try!(Json::from_str(s)))
+    }
+
+    pub fn from_file(path: &Path) -> Result<Configuration, ConfigurationError> {
+        let mut string = String::new();
+        let mut file = try!(File::open(path));
+        try!(file.read_to_string(&mut string));
+        Configuration::from_string(&string)
+    }
+
+    pub fn to_file(&self, path: &Path) -> Result<(), ConfigurationError> {
+        let mut file = try!(File::create(path));
+        try!(writeln!(file, "{}", self.to_json()));
+        Ok(())
+    }
+}
+
+impl ToJson for Configuration {
+    fn to_json(&self) -> Json {
+        let mut obj = BTreeMap::new();
+        obj.insert("state".to_string(), Json::String(self.state.clone()));
+        obj.insert("head".to_string(), Json::I64(self.head as i64));
+        obj.insert("tape".to_string(), Json::String(self.tape.clone()));
+        obj.insert("tape_start".to_string(), Json::I64(self.tape_start as i64));
+        obj.insert("steps".to_string(), Json::U64(self.steps));
+        Json::Object(obj)
+    }
+}
+
+impl<'a> TM<'a> {
+    /// Continue a run of a machine from a saved configuration.
+    pub fn from_configuration(desc: &'a TMDesc, config: &Configuration)
+            -> Result<TM<'a>, ConfigurationError> {
+        let state_index = try!(config.check(desc));
+        let mut tape = Tape::from_str_at(&config.tape, config.tape_start, desc.blank_symbol());
+        tape.ensure_space(config.head);
+
+        Ok(TM {
+            desc: desc,
+            head: config.head,
+            tape: tape,
+            state: &desc.states[state_index],
+            state_index: state_index,
+            steps: config.steps,
+            last_step: None,
+            history: None
+        })
+    }
+
+    pub fn configuration(&self) -> Configuration {
+        Configuration::new(&self.state.name, self.head, &self.tape, self.steps)
+    }
+}
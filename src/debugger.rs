@@ -0,0 +1,309 @@
+//! An interactive debugger for turing machines.
+
+use std::io::{self, BufRead, Write};
+use {TM, StepOutcome, RunError, RenderStyle};
+
+/// A condition that stops `Debugger::step` and `Debugger::cont`. It is
+/// checked after every step.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Breakpoint {
+    /// The machine enters the state with this index.
+    State(usize),
+    /// The machine is in the state with this index, and reads the symbol.
+    StateSymbol(usize, char),
+    /// The head reaches this position.
+    Head(isize),
+    /// This many steps have been executed.
+    Step(u64)
+}
+
+impl Breakpoint {
+    fn hit(&self, tm: &TM) -> bool {
+        match *self {
+            Breakpoint::State(index) => tm.state_index() == index,
+            Breakpoint::StateSymbol(index, sym) =>
+                tm.state_index() == index && tm.tape()[tm.head()] == sym,
+            Breakpoint::Head(pos) => tm.head() == pos,
+            Breakpoint::Step(n) => tm.steps() == n
+        }
+    }
+
+    fn describe(&self, tm: &TM) -> String {
+        let states = &tm.desc().states;
+        match *self {
+            Breakpoint::State(index) => format!("state {}", states[index].name),
+            Breakpoint::StateSymbol(index, sym) =>
+                format!("state {} on '{}'", states[index].name, sym),
+            Breakpoint::Head(pos) => format!("head at {}", pos),
+            Breakpoint::Step(n) => format!("step {}", n)
+        }
+    }
+}
+
+/// Why `Debugger::step` or `Debugger::cont` stopped.
+#[derive(Debug, Clone)]
+pub enum Stop {
+    /// All the requested steps were executed.
+    Done,
+    /// The breakpoint with this index was hit.
+    Breakpoint(usize),
+    /// The machine halted or got stuck.
+    Finished(StepOutcome),
+    Crashed(RunError)
+}
+
+pub struct Debugger<'a> {
+    tm: TM<'a>,
+    breakpoints: Vec<Breakpoint>,
+    /// How many cells to show to each side of the head
+    window: usize,
+    style: RenderStyle
+}
+
+static HELP: &'static str = "\
+step [N]                  execute N steps (default: 1)
+continue                  run until a breakpoint is hit or the machine stops
+back [N]                  go back N steps (default: 1)
+goto N                    go to step N, backwards or forwards
+break state NAME [SYM]    stop in state NAME (when reading SYM)
+break head POS            stop when the head reaches POS
+break step N              stop after N steps
+delete N                  delete breakpoint N
+breakpoints               list all breakpoints
+tape [N]                  show N cells to each side of the head
+info                      show the state, head and the next transition
+quit                      leave the debugger
+";
+
+/// How often the debugger saves checkpoints for `goto`
+const CHECKPOINT_INTERVAL: u64 = 100000;
+
+impl<'a> Debugger<'a> {
+    /// Start debugging a machine. From now on, its history is recorded.
+    pub fn new(mut tm: TM<'a>) -> Debugger<'a> {
+        tm.record_history(CHECKPOINT_INTERVAL);
+        Debugger {
+            tm: tm,
+            breakpoints: vec![],
+            window: 10,
+            style: RenderStyle::Plain
+        }
+    }
+
+    /// Set how the tape is shown, e.g. with colors for terminals.
+    pub fn set_style(&mut self, style: RenderStyle) {
+        self.style = style;
+    }
+
+    pub fn tm(&self) -> &TM<'a> {
+        &self.tm
+    }
+
+    pub fn into_tm(self) -> TM<'a> {
+        self.tm
+    }
+
+    pub fn breakpoints(&self) -> &[Breakpoint] {
+        &self.breakpoints
+    }
+
+    /// Add a breakpoint, and return its index.
+    pub fn add_breakpoint(&mut self, bp: Breakpoint) -> usize {
+        self.breakpoints.push(bp);
+        self.breakpoints.len() - 1
+    }
+
+    pub fn remove_breakpoint(&mut self, index: usize) -> Option<Breakpoint> {
+        if index < self.breakpoints.len() {
+            Some(self.breakpoints.remove(index))
+        } else {
+            None
+        }
+    }
+
+    /// Execute up to `n` steps, or an unlimited number if `n` is `None`.
+    pub fn run(&mut self, n: Option<u64>) -> Stop {
+        let mut done = 0;
+        while n.map_or(true, |n| done < n) {
+            match self.tm.run_step() {
+                Ok(StepOutcome::Continue) => (),
+                Ok(outcome) => {
+                    // The step into a final state is still a step, so
+                    // breakpoints on it should work.
+                    if self.tm.last_step().is_none() {
+                        return Stop::Finished(outcome);
+                    }
+                }
+                Err(e) => return Stop::Crashed(e)
+            }
+            done += 1;
+
+            let tm = &self.tm;
+            if let Some(index) = self.breakpoints.iter().position(|bp| bp.hit(tm)) {
+                return Stop::Breakpoint(index);
+            }
+        }
+        Stop::Done
+    }
+
+    pub fn step(&mut self, n: u64) -> Stop {
+        self.run(Some(n))
+    }
+
+    pub fn cont(&mut self) -> Stop {
+        self.run(None)
+    }
+
+    /// Read commands from `input` until it ends or `quit` is entered.
+    pub fn repl<R: BufRead, W: Write>(&mut self, input: R, output: &mut W) -> io::Result<()> {
+        try!(write!(output, "(tm) "));
+        try!(output.flush());
+        for line in input.lines() {
+            if !try!(self.execute(&try!(line), output)) {
+                return Ok(());
+            }
+            try!(write!(output, "(tm) "));
+            try!(output.flush());
+        }
+        writeln!(output, "")
+    }
+
+    /// Execute one command, and write its results to `output`. Returns
+    /// `false` if the debugger should quit.
+    pub fn execute<W: Write>(&mut self, line: &str, output: &mut W) -> io::Result<bool> {
+        let words = line.split_whitespace().collect::<Vec<_>>();
+        if words.is_empty() {
+            return Ok(true);
+        }
+
+        let args = &words[1..];
+        match words[0] {
+            "s" | "step" => {
+                let n = match args.get(0).map(|n| n.parse()) {
+                    None => 1,
+                    Some(Ok(n)) => n,
+                    Some(Err(_)) => return self.usage(output)
+                };
+                let stop = self.step(n);
+                try!(self.report(stop, output));
+            }
+            "c" | "continue" => {
+                let stop = self.cont();
+                try!(self.report(stop, output));
+            }
+            "back" => {
+                let n = match args.get(0).map(|n| n.parse()) {
+                    None => 1,
+                    Some(Ok(n)) => n,
+                    Some(Err(_)) => return self.usage(output)
+                };
+                for _ in 0..n {
+                    if !self.tm.step_back() {
+                        try!(writeln!(output, "can't go back any further"));
+                        break;
+                    }
+                }
+                try!(self.print_info(output));
+            }
+            "goto" if args.len() == 1 => {
+                let n = match args[0].parse() {
+                    Ok(n) => n,
+                    Err(_) => return self.usage(output)
+                };
+                let stop = match self.tm.goto_step(n) {
+                    Ok(StepOutcome::Continue) => Stop::Done,
+                    Ok(_) if self.tm.steps() == n => Stop::Done,
+                    Ok(outcome) => Stop::Finished(outcome),
+                    Err(e) => Stop::Crashed(e)
+                };
+                try!(self.report(stop, output));
+            }
+            "b" | "break" => {
+                let bp = match self.parse_breakpoint(args) {
+                    Some(bp) => bp,
+                    None => return self.usage(output)
+                };
+                try!(writeln!(output, "breakpoint {}: {}", self.breakpoints.len(),
+                              bp.describe(&self.tm)));
+                self.add_breakpoint(bp);
+            }
+            "d" | "delete" if args.len() == 1 => {
+                match args[0].parse().ok().and_then(|n| self.remove_breakpoint(n)) {
+                    Some(_) => (),
+                    None => try!(writeln!(output, "no breakpoint {}", args[0]))
+                }
+            }
+            "breakpoints" => {
+                for (i, bp) in self.breakpoints.iter().enumerate() {
+                    try!(writeln!(output, "{}: {}", i, bp.describe(&self.tm)));
+                }
+            }
+            "t" | "tape" => {
+                match args.get(0).map(|n| n.parse()) {
+                    None => (),
+                    Some(Ok(n)) => self.window = n,
+                    Some(Err(_)) => return self.usage(output)
+                }
+                try!(self.print_tape(output));
+            }
+            "i" | "info" => try!(self.print_info(output)),
+            "h" | "help" => try!(write!(output, "{}", HELP)),
+            "q" | "quit" => return Ok(false),
+            _ => return self.usage(output)
+        }
+
+        Ok(true)
+    }
+
+    fn usage<W: Write>(&self, output: &mut W) -> io::Result<bool> {
+        try!(writeln!(output, "invalid command, try `help'"));
+        Ok(true)
+    }
+
+    fn parse_breakpoint(&self, args: &[&str]) -> Option<Breakpoint> {
+        let desc = self.tm.desc();
+        if args.len() < 2 {
+            return None;
+        }
+        match (args[0], args.len()) {
+            ("state", 2) => desc.find_state(args[1]).map(Breakpoint::State),
+            ("state", 3) if args[2].chars().count() == 1 => {
+                let sym = args[2].chars().next().unwrap();
+                desc.find_state(args[1]).map(|i| Breakpoint::StateSymbol(i, sym))
+            }
+            ("head", 2) => args[1].parse().ok().map(Breakpoint::Head),
+            ("step", 2) => args[1].parse().ok().map(Breakpoint::Step),
+            _ => None
+        }
+    }
+
+    fn report<W: Write>(&self, stop: Stop, output: &mut W) -> io::Result<()> {
+        match stop {
+            Stop::Done => (),
+            Stop::Breakpoint(i) =>
+                try!(writeln!(output, "breakpoint {}: {}", i,
+                              self.breakpoints[i].describe(&self.tm))),
+            Stop::Finished(StepOutcome::Halted(kind)) =>
+                try!(writeln!(output, "halted ({:?})", kind)),
+            Stop::Finished(_) =>
+                try!(writeln!(output, "stuck, there is no transition")),
+            Stop::Crashed(e) => try!(writeln!(output, "crashed: {}", e))
+        }
+        self.print_info(output)
+    }
+
+    fn print_info<W: Write>(&self, output: &mut W) -> io::Result<()> {
+        let tm = &self.tm;
+        try!(writeln!(output, "step {}, state {}, head at {}, reading '{}'",
+                      tm.steps(), tm.state().name, tm.head(), tm.tape()[tm.head()]));
+        match tm.current_transition() {
+            Some(t) => writeln!(output, "next: write '{}', move {}, go to {}",
+                                t.symbol, t.movement.to_char(), t.state),
+            None => writeln!(output, "next: none")
+        }
+    }
+
+    fn print_tape<W: Write>(&self, output: &mut W) -> io::Result<()> {
+        writeln!(output, "{}", self.tm.render(Some(self.window), self.style))
+    }
+}
@@ -0,0 +1,348 @@
+//! A compiled form of turing machines for fast interpretation.
+//!
+//! `DenseTable` turns the transitions of a `TMDesc` into one flat array that
+//! is indexed by state and symbol, so that a step is a single lookup.
+//! `FastTM` runs such a table on a tape of symbol indices instead of `char`s.
+
+use std::{cmp, u32, u64};
+use {TMDesc, State, HaltKind, Movement, Tape, StepOutcome, Configuration};
+use {RunError, RunErrorKind, TableError, Diagnostic, ConfigurationError};
+
+// A table entry packs a whole transition into 32 bits:
+// bits 0-1 are the movement, bits 2-9 the symbol to write, and bits 10-31 the
+// next state.
+const MOVE_LEFT: u32 = 0;
+const MOVE_NONE: u32 = 1;
+const MOVE_RIGHT: u32 = 2;
+const SYMBOL_SHIFT: u32 = 2;
+const STATE_SHIFT: u32 = 10;
+
+/// The entry for missing transitions, and for all symbols in final states.
+/// Valid entries never look like this, because their movement isn't 3.
+const NO_TRANSITION: u32 = u32::MAX;
+
+/// One symbol index is reserved for symbols that aren't input symbols.
+const MAX_SYMBOLS: usize = 255;
+const MAX_STATES: usize = 1 << (32 - STATE_SHIFT);
+
+pub struct DenseTable<'a> {
+    desc: &'a TMDesc,
+    /// The entry for state `q` and symbol `s` is at `q * width + s`.
+    entries: Vec<u32>,
+    /// The number of symbols, plus one for unknown symbols
+    width: usize,
+    halt: Vec<Option<HaltKind>>
+}
+
+impl<'a> DenseTable<'a> {
+    pub fn new(desc: &'a TMDesc) -> Result<DenseTable<'a>, TableError> {
+        let num_symbols = desc.input_symbols.len();
+        if num_symbols == 0 {
+            return Err(TableError::Invalid(Diagnostic::NoSymbols));
+        } else if num_symbols > MAX_SYMBOLS {
+            return Err(TableError::TooManySymbols(num_symbols));
+        }
+        if desc.states.is_empty() {
+            return Err(TableError::Invalid(Diagnostic::NoStates));
+        } else if desc.states.len() > MAX_STATES {
+            return Err(TableError::TooManyStates(desc.states.len()));
+        }
+
+        let width = num_symbols + 1;
+        let mut entries = Vec::with_capacity(desc.states.len() * width);
+        for state in desc.states.iter() {
+            if state.is_final() {
+                entries.extend((0..width).map(|_| NO_TRANSITION));
+                continue;
+            }
+            if state.transitions.len() != num_symbols {
+                return Err(TableError::Invalid(Diagnostic::RowLength {
+                    state: state.name.clone(),
+                    expected: num_symbols,
+                    found: state.transitions.len()
+                }));
+            }
+
+            let pairs = state.transitions.iter().zip(desc.input_symbols.iter());
+            for (trans, &symbol) in pairs {
+                let trans = match *trans {
+                    Some(ref trans) => trans,
+                    None => {
+                        entries.push(NO_TRANSITION);
+                        continue;
+                    }
+                };
+                let next = match trans.state_index.or_else(|| desc.find_state(&trans.state)) {
+                    Some(next) => next,
+                    None => return Err(TableError::Invalid(Diagnostic::UnknownState {
+                        state: state.name.clone(),
+                        symbol: symbol,
+                        target: trans.state.clone()
+                    }))
+                };
+                let written = match desc.input_symbols.iter().position(|&s| s == trans.symbol) {
+                    Some(written) => written,
+                    None => return Err(TableError::Invalid(Diagnostic::UnknownSymbol {
+                        state: state.name.clone(),
+                        symbol: symbol,
+                        written: trans.symbol
+                    }))
+                };
+                let movement = match trans.movement {
+                    Movement::Left => MOVE_LEFT,
+                    Movement::None => MOVE_NONE,
+                    Movement::Right => MOVE_RIGHT
+                };
+                entries.push((next as u32) << STATE_SHIFT |
+                             (written as u32) << SYMBOL_SHIFT |
+                             movement);
+            }
+            entries.push(NO_TRANSITION);
+        }
+
+        Ok(DenseTable {
+            desc: desc,
+            entries: entries,
+            width: width,
+            halt: desc.states.iter().map(|s| s.halt).collect()
+        })
+    }
+
+    pub fn desc(&self) -> &'a TMDesc {
+        self.desc
+    }
+
+    /// The index of the blank symbol.
+    pub fn blank(&self) -> u8 {
+        (self.width - 2) as u8
+    }
+
+    /// The index that stands for all symbols that aren't input symbols.
+    pub fn unknown(&self) -> u8 {
+        (self.width - 1) as u8
+    }
+
+    pub fn symbol_index(&self, c: char) -> u8 {
+        match self.desc.input_symbols.iter().position(|&s| s == c) {
+            Some(index) => index as u8,
+            None => self.unknown()
+        }
+    }
+
+    /// The symbol with the given index, unless it's `unknown()`.
+    pub fn symbol(&self, index: u8) -> Option<char> {
+        self.desc.input_symbols.get(index as usize).map(|&c| c)
+    }
+
+    pub fn halt(&self, state: usize) -> Option<HaltKind> {
+        self.halt[state]
+    }
+
+    /// The transition of `state` on `symbol`: the next state, the symbol to
+    /// write and the movement.
+    pub fn lookup(&self, state: usize, symbol: u8) -> Option<(usize, u8, Movement)> {
+        let entry = self.entries[state * self.width + symbol as usize];
+        if entry == NO_TRANSITION {
+            return None;
+        }
+        let movement = match entry & 3 {
+            MOVE_LEFT => Movement::Left,
+            MOVE_RIGHT => Movement::Right,
+            _ => Movement::None
+        };
+        Some(((entry >> STATE_SHIFT) as usize,
+              (entry >> SYMBOL_SHIFT) as u8,
+              movement))
+    }
+}
+
+/// Like `TM`, but much faster, and without any of the debugging facilities.
+pub struct FastTM<'a> {
+    table: DenseTable<'a>,
+    tape: Vec<u8>,
+    /// The index of position 0 in `tape`
+    origin: usize,
+    /// The index of the head in `tape`
+    head: usize,
+    /// The part of `tape` that has been used, like the tape of a `TM`. The
+    /// rest is room to grow.
+    low: usize,
+    high: usize,
+    state: usize,
+    steps: u64,
+    /// Unknown symbols can only come from the input, so they are looked up
+    /// here for error messages.
+    input: Vec<char>,
+    /// The position of the first symbol of `input`
+    input_start: isize
+}
+
+impl<'a> FastTM<'a> {
+    pub fn new(desc: &'a TMDesc, input: &str) -> Result<FastTM<'a>, TableError> {
+        let table = try!(DenseTable::new(desc));
+        Ok(FastTM::with_tape(table, input.chars().collect(), 0, 0, 0, 0))
+    }
+
+    /// Continue a run of a machine from a saved configuration.
+    pub fn from_configuration(desc: &'a TMDesc, config: &Configuration)
+            -> Result<FastTM<'a>, ConfigurationError> {
+        let state = try!(config.check(desc));
+        let table = try!(DenseTable::new(desc).map_err(ConfigurationError::Table));
+        Ok(FastTM::with_tape(table, config.tape.chars().collect(), config.tape_start,
+                             config.head, state, config.steps))
+    }
+
+    /// A machine whose tape holds `input` from position `start` on. Like the
+    /// tape of a `TM`, the used part always includes position 0 and the
+    /// head.
+    fn with_tape(table: DenseTable<'a>, input: Vec<char>, start: isize, head: isize,
+                 state: usize, steps: u64) -> FastTM<'a> {
+        let low = cmp::min(cmp::min(start, head), 0);
+        let high = cmp::max(cmp::max(start + input.len() as isize, head + 1), 0);
+        let mut tape = vec![table.blank(); (high - low) as usize];
+        for (i, &c) in input.iter().enumerate() {
+            tape[(start - low) as usize + i] = table.symbol_index(c);
+        }
+
+        FastTM {
+            table: table,
+            tape: tape,
+            origin: -low as usize,
+            head: (head - low) as usize,
+            low: 0,
+            high: (high - low) as usize,
+            state: state,
+            steps: steps,
+            input: input,
+            input_start: start
+        }
+    }
+
+    /// The symbol that the tape started with at a position, for symbols
+    /// that aren't input symbols.
+    fn input_symbol(&self, position: isize) -> char {
+        self.input[(position - self.input_start) as usize]
+    }
+
+    /// Run until the machine halts or gets stuck, or until `max_steps` steps
+    /// have been executed in total. In the latter case, `Continue` is
+    /// returned.
+    pub fn run(&mut self, max_steps: Option<u64>) -> Result<StepOutcome, RunError> {
+        let limit = max_steps.unwrap_or(u64::MAX);
+        let blank = self.table.blank();
+        {
+            let FastTM { ref table, ref mut tape, ref mut origin, .. } = *self;
+            let entries = &table.entries[..];
+            let width = table.width;
+            let mut head = self.head;
+            let mut low = self.low;
+            let mut high = self.high;
+            let mut state = self.state;
+            let mut steps = self.steps;
+
+            while steps < limit {
+                let entry = entries[state * width + tape[head] as usize];
+                if entry == NO_TRANSITION {
+                    break;
+                }
+                tape[head] = (entry >> SYMBOL_SHIFT) as u8;
+                state = (entry >> STATE_SHIFT) as usize;
+                steps += 1;
+
+                match entry & 3 {
+                    MOVE_LEFT => {
+                        if head == 0 {
+                            let extra = grow_left(tape, blank);
+                            *origin += extra;
+                            head += extra;
+                            low += extra;
+                            high += extra;
+                        }
+                        head -= 1;
+                        if head < low {
+                            low = head;
+                        }
+                    }
+                    MOVE_RIGHT => {
+                        head += 1;
+                        if head == high {
+                            high += 1;
+                            if head == tape.len() {
+                                grow_right(tape, blank);
+                            }
+                        }
+                    }
+                    _ => ()
+                }
+            }
+
+            self.head = head;
+            self.low = low;
+            self.high = high;
+            self.state = state;
+            self.steps = steps;
+        }
+
+        if let Some(kind) = self.table.halt(self.state) {
+            Ok(StepOutcome::Halted(kind))
+        } else if self.tape[self.head] == self.table.unknown() {
+            Err(RunError {
+                kind: RunErrorKind::UnknownSymbol,
+                state: self.state().name.clone(),
+                head: self.head(),
+                symbol: self.input_symbol(self.head()),
+                step: self.steps
+            })
+        } else if self.table.lookup(self.state, self.tape[self.head]).is_none() {
+            Ok(StepOutcome::Stuck)
+        } else {
+            Ok(StepOutcome::Continue)
+        }
+    }
+
+    pub fn table(&self) -> &DenseTable<'a> {
+        &self.table
+    }
+
+    pub fn steps(&self) -> u64 {
+        self.steps
+    }
+
+    pub fn head(&self) -> isize {
+        self.head as isize - self.origin as isize
+    }
+
+    pub fn state(&self) -> &'a State {
+        &self.table.desc.states[self.state]
+    }
+
+    pub fn state_index(&self) -> usize {
+        self.state
+    }
+
+    /// A copy of the tape, with symbols instead of indices.
+    pub fn tape(&self) -> Tape {
+        let desc = self.table.desc;
+        let s = (self.low..self.high).map(|i| {
+            match self.table.symbol(self.tape[i]) {
+                Some(c) => c,
+                None => self.input_symbol(i as isize - self.origin as isize)
+            }
+        }).collect::<String>();
+        Tape::from_str_at(&s, self.low as isize - self.origin as isize, desc.blank_symbol())
+    }
+}
+
+/// Add blanks to the left end of the tape, and return how many.
+fn grow_left(tape: &mut Vec<u8>, blank: u8) -> usize {
+    let extra = cmp::max(tape.len(), 16);
+    let mut new = vec![blank; extra + tape.len()];
+    new[extra..].copy_from_slice(tape);
+    *tape = new;
+    extra
+}
+
+fn grow_right(tape: &mut Vec<u8>, blank: u8) {
+    let len = tape.len();
+    tape.resize(len + cmp::max(len, 16), blank);
+}
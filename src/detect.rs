@@ -0,0 +1,267 @@
+//! Detecting machines that never halt.
+//!
+//! Two kinds of machines are recognized:
+//!
+//! * Cyclers, which come back to exactly the same configuration. They are
+//!   found with Brent's algorithm: a configuration is saved at every power of
+//!   two steps and compared with all later ones. To keep that cheap, the tape
+//!   has a hash that is updated with every step, and only configurations with
+//!   the same hash are compared cell by cell.
+//!
+//! * Translated cyclers, which repeat the same behaviour further and further
+//!   out into the blank part of the tape. Whenever the head visits a new cell
+//!   beyond the used part of the tape (a record), the configuration is saved
+//!   for the current state. If the previous record in the same state had the
+//!   same tape contents, as far back as the head went since then, the machine
+//!   will go on doing the same thing forever. Instead of a copy of the tape,
+//!   records keep the old symbols of the cells that are overwritten later, so
+//!   that they stay small for machines that keep extending the tape.
+
+use std::{cmp, fmt};
+use std::collections::HashMap;
+use {TM, Tape, StepOutcome, HaltKind, RunError};
+
+/// Why a machine never halts.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NonHaltingReason {
+    /// The machine reached the same configuration twice.
+    Cycle,
+    /// The machine repeats itself, moving further into the blank part of the
+    /// tape every time.
+    TranslatedCycle
+}
+
+impl fmt::Display for NonHaltingReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            NonHaltingReason::Cycle => write!(f, "cycle"),
+            NonHaltingReason::TranslatedCycle => write!(f, "translated cycle")
+        }
+    }
+}
+
+/// How `TM::run_until` ended.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RunOutcome {
+    Halted(HaltKind),
+    Stuck,
+    /// The step limit was reached, and nothing is known about the machine.
+    LimitReached,
+    /// The machine will never halt. It repeats itself every `period` steps.
+    NonHalting { reason: NonHaltingReason, period: u64 }
+}
+
+struct Snapshot {
+    steps: u64,
+    state: usize,
+    head: isize,
+    hash: u64,
+    tape: Tape
+}
+
+/// The configuration when the head visited a new cell.
+struct Record {
+    steps: u64,
+    head: isize,
+    /// The symbols that cells had at the time of the record, for the cells
+    /// that were overwritten since then. The others are still the same.
+    overwritten: HashMap<isize, char>,
+    /// How far the head went back (towards the used part of the tape) since
+    /// this record.
+    furthest_back: isize
+}
+
+/// Watches a `TM` step by step, and tells when it will never halt.
+///
+/// The detector has to see every step, from the configuration it was
+/// created with onwards, and it doesn't know about steps that are undone.
+pub struct LoopDetector {
+    hash: u64,
+    saved: Snapshot,
+    /// The next snapshot is saved after `power` steps.
+    power: u64,
+
+    /// The used part of the tape
+    min_head: isize,
+    max_head: isize,
+    /// Records to the right and to the left, by state
+    right: Vec<Option<Record>>,
+    left: Vec<Option<Record>>,
+    /// The range that the head has covered since the last record
+    since_min: isize,
+    since_max: isize
+}
+
+impl LoopDetector {
+    pub fn new(tm: &TM) -> LoopDetector {
+        let tape = &tm.tape;
+        let hash = (tape.min()..tape.max()).fold(0u64, |hash, i| {
+            hash.wrapping_add(cell_hash(i, tape[i], tape.blank()))
+        });
+        let num_states = tm.desc.states.len();
+
+        LoopDetector {
+            hash: hash,
+            saved: Snapshot {
+                steps: tm.steps,
+                state: tm.state_index,
+                head: tm.head,
+                hash: hash,
+                tape: tape.clone()
+            },
+            power: 1,
+            min_head: tape.min(),
+            max_head: tape.max() - 1,
+            right: (0..num_states).map(|_| None).collect(),
+            left: (0..num_states).map(|_| None).collect(),
+            since_min: tm.head,
+            since_max: tm.head
+        }
+    }
+
+    /// Look at the step that `tm` just executed. Returns the reason and the
+    /// period if the machine is now known to never halt.
+    pub fn observe(&mut self, tm: &TM) -> Option<(NonHaltingReason, u64)> {
+        let step = match tm.last_step {
+            Some(step) => step,
+            None => return None
+        };
+        let blank = tm.tape.blank();
+        self.hash = self.hash
+            .wrapping_sub(cell_hash(step.head, step.read, blank))
+            .wrapping_add(cell_hash(step.head, step.written, blank));
+        if step.read != step.written {
+            let records = self.right.iter_mut().chain(self.left.iter_mut());
+            for record in records.filter_map(|r| r.as_mut()) {
+                record.overwritten.entry(step.head).or_insert(step.read);
+            }
+        }
+
+        if let Some(period) = self.check_cycle(tm) {
+            return Some((NonHaltingReason::Cycle, period));
+        }
+        self.check_records(tm).map(|period| (NonHaltingReason::TranslatedCycle, period))
+    }
+
+    fn check_cycle(&mut self, tm: &TM) -> Option<u64> {
+        let period = tm.steps - self.saved.steps;
+        if tm.state_index == self.saved.state && tm.head == self.saved.head &&
+                self.hash == self.saved.hash {
+            let saved = &self.saved.tape;
+            let from = cmp::min(tm.tape.min(), saved.min());
+            let to = cmp::max(tm.tape.max(), saved.max());
+            if same_cells(saved, &tm.tape, from, to, 0) {
+                return Some(period);
+            }
+        }
+
+        if period == self.power {
+            self.saved = Snapshot {
+                steps: tm.steps,
+                state: tm.state_index,
+                head: tm.head,
+                hash: self.hash,
+                tape: tm.tape.clone()
+            };
+            self.power *= 2;
+        }
+        None
+    }
+
+    fn check_records(&mut self, tm: &TM) -> Option<u64> {
+        let head = tm.head;
+        self.since_min = cmp::min(self.since_min, head);
+        self.since_max = cmp::max(self.since_max, head);
+
+        let to_right = if head > self.max_head {
+            self.max_head = head;
+            true
+        } else if head < self.min_head {
+            self.min_head = head;
+            false
+        } else {
+            return None;
+        };
+
+        for record in self.right.iter_mut().filter_map(|r| r.as_mut()) {
+            record.furthest_back = cmp::min(record.furthest_back, self.since_min);
+        }
+        for record in self.left.iter_mut().filter_map(|r| r.as_mut()) {
+            record.furthest_back = cmp::max(record.furthest_back, self.since_max);
+        }
+        self.since_min = head;
+        self.since_max = head;
+
+        let records = if to_right { &mut self.right } else { &mut self.left };
+        let new = Record {
+            steps: tm.steps,
+            head: head,
+            overwritten: HashMap::new(),
+            furthest_back: head
+        };
+        let old = match records[tm.state_index].take() {
+            Some(old) => old,
+            None => {
+                records[tm.state_index] = Some(new);
+                return None;
+            }
+        };
+
+        // The old record was in the same state, and everything beyond the
+        // head is blank in both cases. If the cells back to where the head
+        // went are the same too, the machine will do the same again.
+        let shift = head - old.head;
+        let (from, to) = if to_right {
+            (old.furthest_back, old.head + 1)
+        } else {
+            (old.head, old.furthest_back + 1)
+        };
+        let period = tm.steps - old.steps;
+        records[tm.state_index] = Some(new);
+        let old_cell = |i| old.overwritten.get(&i).cloned().unwrap_or_else(|| tm.tape.get(i));
+        if (from..to).all(|i| old_cell(i) == tm.tape.get(i + shift)) {
+            Some(period)
+        } else {
+            None
+        }
+    }
+}
+
+/// Whether the cells of `a` from `from` up to (but not including) `to` are
+/// the same as those of `b`, `shift` cells further right.
+fn same_cells(a: &Tape, b: &Tape, from: isize, to: isize, shift: isize) -> bool {
+    (from..to).all(|i| a.get(i) == b.get(i + shift))
+}
+
+/// The contribution of one cell to the hash of the tape. Blanks don't count,
+/// so that the hash doesn't depend on how much of the tape is used.
+fn cell_hash(index: isize, symbol: char, blank: char) -> u64 {
+    if symbol == blank {
+        return 0;
+    }
+    // splitmix64
+    let mut x = (index as u64).wrapping_mul(0x9e3779b97f4a7c15) ^ symbol as u64;
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d049bb133111eb);
+    x ^ (x >> 31)
+}
+
+impl<'a> TM<'a> {
+    /// Run until the machine halts or gets stuck, until `limit` steps have
+    /// been executed in total, or until it is clear that the machine will
+    /// never halt.
+    pub fn run_until(&mut self, limit: u64) -> Result<RunOutcome, RunError> {
+        let mut detector = LoopDetector::new(self);
+        while self.steps < limit {
+            match try!(self.run_step()) {
+                StepOutcome::Continue => (),
+                StepOutcome::Halted(kind) => return Ok(RunOutcome::Halted(kind)),
+                StepOutcome::Stuck => return Ok(RunOutcome::Stuck)
+            }
+            if let Some((reason, period)) = detector.observe(self) {
+                return Ok(RunOutcome::NonHalting { reason: reason, period: period });
+            }
+        }
+        Ok(RunOutcome::LimitReached)
+    }
+}
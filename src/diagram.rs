@@ -0,0 +1,217 @@
+//! Space-time diagrams: pictures of the tape over time, with one row per
+//! recorded step and one column per cell.
+//!
+//! Cells are colored by their symbol, with blanks in white. Optionally, the
+//! cell under the head is colored by the state instead. Diagrams can be
+//! written as PBM (black and white, every symbol but the blank is black), PPM
+//! or SVG, which all need no libraries.
+
+use std::cmp;
+use std::io::{self, Write};
+use {TM, TMDesc};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DiagramFormat {
+    Pbm,
+    Ppm,
+    Svg
+}
+
+impl DiagramFormat {
+    pub fn parse(s: &str) -> Option<DiagramFormat> {
+        match s {
+            "pbm" => Some(DiagramFormat::Pbm),
+            "ppm" => Some(DiagramFormat::Ppm),
+            "svg" => Some(DiagramFormat::Svg),
+            _ => None
+        }
+    }
+}
+
+type Color = (u8, u8, u8);
+
+const WHITE: Color = (255, 255, 255);
+
+/// Colors for the symbols other than the blank, in the order of the header.
+static SYMBOL_COLORS: [Color; 8] = [
+    (0, 0, 0), (214, 39, 40), (31, 119, 180), (44, 160, 44),
+    (255, 127, 14), (148, 103, 189), (140, 86, 75), (127, 127, 127)
+];
+
+/// The most rows that a diagram keeps. Each row holds the whole tape, so long
+/// runs would take too much memory otherwise.
+const MAX_ROWS: usize = 4096;
+
+/// The tape after one step
+struct Row {
+    step: u64,
+    /// The position of the first cell of `cells`
+    start: isize,
+    cells: Vec<char>,
+    head: isize,
+    state: usize
+}
+
+/// Records the tape of a machine over time.
+pub struct SpaceTime {
+    rows: Vec<Row>,
+    /// Only every `every`th step is recorded.
+    every: u64,
+    color_states: bool
+}
+
+impl SpaceTime {
+    /// Record every `every`th step. If `color_states` is set, the cell under
+    /// the head shows the state rather than the symbol.
+    ///
+    /// Once there are more than `MAX_ROWS` rows, only every second one is
+    /// kept, and from then on only every `2 * every`th step is recorded.
+    pub fn new(every: u64, color_states: bool) -> SpaceTime {
+        assert!(every > 0);
+        SpaceTime {
+            rows: vec![],
+            every: every,
+            color_states: color_states
+        }
+    }
+
+    /// Add a row for the current configuration of `tm`, unless this step is
+    /// to be skipped. This should be called before the first step, and after
+    /// every step.
+    pub fn record(&mut self, tm: &TM) {
+        if tm.steps() % self.every != 0 {
+            return;
+        }
+        let tape = tm.tape();
+        self.rows.push(Row {
+            step: tm.steps(),
+            start: tape.min(),
+            cells: (tape.min()..tape.max()).map(|i| tape[i]).collect(),
+            head: tm.head(),
+            state: tm.state_index()
+        });
+
+        if self.rows.len() > MAX_ROWS {
+            self.every *= 2;
+            let every = self.every;
+            self.rows.retain(|row| row.step % every == 0);
+        }
+    }
+
+    /// How many steps there are between the rows, which grows when there are
+    /// too many rows.
+    pub fn every(&self) -> u64 {
+        self.every
+    }
+
+    /// The number of rows recorded so far.
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn write<W: Write>(&self, desc: &TMDesc, format: DiagramFormat, out: &mut W)
+            -> io::Result<()> {
+        let (from, to) = self.columns();
+        let pixels = self.rows.iter().map(|row| {
+            (from..to).map(|i| self.color(desc, row, i)).collect::<Vec<_>>()
+        }).collect::<Vec<_>>();
+        let width = (to - from) as usize;
+        let height = pixels.len();
+
+        match format {
+            DiagramFormat::Pbm => {
+                try!(write!(out, "P4\n{} {}\n", width, height));
+                for row in pixels.iter() {
+                    let mut bytes = vec![0u8; (width + 7) / 8];
+                    for (i, &color) in row.iter().enumerate() {
+                        if color != WHITE {
+                            bytes[i / 8] |= 0x80 >> (i % 8);
+                        }
+                    }
+                    try!(out.write_all(&bytes));
+                }
+                Ok(())
+            }
+            DiagramFormat::Ppm => {
+                try!(write!(out, "P6\n{} {}\n255\n", width, height));
+                for row in pixels.iter() {
+                    let mut bytes = Vec::with_capacity(3 * width);
+                    for &(r, g, b) in row.iter() {
+                        bytes.push(r);
+                        bytes.push(g);
+                        bytes.push(b);
+                    }
+                    try!(out.write_all(&bytes));
+                }
+                Ok(())
+            }
+            DiagramFormat::Svg => {
+                try!(writeln!(out, "<svg xmlns=\"http://www.w3.org/2000/svg\" \
+                                    viewBox=\"0 0 {} {}\" shape-rendering=\"crispEdges\">",
+                              width, height));
+                try!(writeln!(out, "<rect width=\"{}\" height=\"{}\" fill=\"#ffffff\"/>",
+                              width, height));
+                for (y, row) in pixels.iter().enumerate() {
+                    // One rectangle for each run of cells with the same color
+                    let mut x = 0;
+                    while x < row.len() {
+                        let color = row[x];
+                        let mut end = x + 1;
+                        while end < row.len() && row[end] == color {
+                            end += 1;
+                        }
+                        if color != WHITE {
+                            try!(writeln!(out, "<rect x=\"{}\" y=\"{}\" width=\"{}\" \
+                                                height=\"1\" fill=\"#{:02x}{:02x}{:02x}\"/>",
+                                          x, y, end - x, color.0, color.1, color.2));
+                        }
+                        x = end;
+                    }
+                }
+                writeln!(out, "</svg>")
+            }
+        }
+    }
+
+    /// The range of cells that were used in any row.
+    fn columns(&self) -> (isize, isize) {
+        self.rows.iter().fold((0, 1), |(from, to), row| {
+            (cmp::min(from, row.start),
+             cmp::max(to, row.start + row.cells.len() as isize))
+        })
+    }
+
+    fn color(&self, desc: &TMDesc, row: &Row, i: isize) -> Color {
+        if self.color_states && i == row.head {
+            return state_color(row.state, desc.states.len());
+        }
+        let symbol = if i >= row.start && i < row.start + row.cells.len() as isize {
+            row.cells[(i - row.start) as usize]
+        } else {
+            desc.blank_symbol()
+        };
+        if symbol == desc.blank_symbol() {
+            return WHITE;
+        }
+        let index = desc.input_symbols.iter().position(|&s| s == symbol)
+            .unwrap_or(SYMBOL_COLORS.len() - 1);
+        SYMBOL_COLORS[index % SYMBOL_COLORS.len()]
+    }
+}
+
+/// A color for each state, with hues spread evenly around the color wheel.
+fn state_color(state: usize, num_states: usize) -> Color {
+    let hue = state as f64 * 6.0 / num_states as f64;
+    let x = 1.0 - (hue % 2.0 - 1.0).abs();
+    let (r, g, b) = match hue as usize {
+        0 => (1.0, x, 0.0),
+        1 => (x, 1.0, 0.0),
+        2 => (0.0, 1.0, x),
+        3 => (0.0, x, 1.0),
+        4 => (x, 0.0, 1.0),
+        _ => (1.0, 0.0, x)
+    };
+    // Not quite as bright, so that the colors are visible on white.
+    let scale = |c: f64| (c * 200.0) as u8;
+    (scale(r), scale(g), scale(b))
+}
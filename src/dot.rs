@@ -0,0 +1,85 @@
+//! Graphviz pictures of the state graph of a `TMDesc`.
+
+use TMDesc;
+
+/// Quote a string for DOT.
+fn quote(s: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' | '\\' => {
+                quoted.push('\\');
+                quoted.push(c);
+            }
+            '\n' => quoted.push_str("\\n"),
+            c => quoted.push(c)
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+impl TMDesc {
+    /// A Graphviz graph of the machine, with one node per state and one edge
+    /// for all the transitions between two states. Edges are labelled with
+    /// `read/write,move` for each transition. Final states are drawn as
+    /// double circles, and states that can't be reached are grey.
+    ///
+    /// Transitions that lead to unknown states, and transitions of final
+    /// states, are left out.
+    pub fn to_dot(&self) -> String {
+        let reachable = self.reachable_states();
+        let mut dot = String::from("digraph tm {\n    rankdir=LR;\n    node [shape=circle];\n");
+
+        // Nodes are called s0, s1, ..., so that any state name can be used
+        // as a label.
+        if !self.states.is_empty() {
+            dot.push_str("    start [shape=point];\n    start -> s0;\n");
+        }
+        for (i, state) in self.states.iter().enumerate() {
+            let mut attrs = vec![format!("label={}", quote(&state.name))];
+            if state.is_final() {
+                attrs.push("shape=doublecircle".into());
+            }
+            if !reachable[i] {
+                attrs.push("color=grey".into());
+                attrs.push("fontcolor=grey".into());
+            }
+            dot.push_str(&format!("    s{} [{}];\n", i, attrs.join(", ")));
+        }
+
+        for (i, state) in self.states.iter().enumerate() {
+            if state.is_final() {
+                continue;
+            }
+            // The labels of the edges to each state, in the order in which
+            // the states are first used.
+            let mut edges: Vec<(usize, Vec<String>)> = vec![];
+            let pairs = state.transitions.iter().zip(self.input_symbols.iter());
+            for (trans, &symbol) in pairs {
+                let trans = match *trans {
+                    Some(ref trans) => trans,
+                    None => continue
+                };
+                let target = match self.find_state(&trans.state) {
+                    Some(target) => target,
+                    None => continue
+                };
+                let label = format!("{}/{},{}", symbol, trans.symbol, trans.movement.to_char());
+                match edges.iter().position(|&(t, _)| t == target) {
+                    Some(index) => edges[index].1.push(label),
+                    None => edges.push((target, vec![label]))
+                }
+            }
+
+            for (target, labels) in edges {
+                let grey = if reachable[i] { "" } else { ", color=grey, fontcolor=grey" };
+                dot.push_str(&format!("    s{} -> s{} [label={}{}];\n",
+                                      i, target, quote(&labels.join("\n")), grey));
+            }
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+}
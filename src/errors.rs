@@ -3,9 +3,123 @@
 use self::TMDescError::*;
 use std::io;
 use std::fmt::{self, Display, Formatter};
+use validate::Diagnostic;
+use format::Format;
+use rustc_serialize::json::ParserError;
 
+/// Everything that can go wrong while loading a `TMDesc`.
+///
+/// Apart from `Io`, all variants refer to a position in the source text:
+/// `line` and `column` are 1-based, and `column` counts characters, not bytes.
+/// `text` is the offending piece of the input.
+#[derive(Debug)]
 pub enum TMDescError {
-    Io(io::Error)
+    Io(io::Error),
+
+    /// A transition doesn't consist of exactly three comma-separated parts.
+    BadTransitionArity { line: usize, column: usize, text: String },
+
+    /// A symbol (in the header or in a transition) is not exactly one
+    /// character long.
+    MultiCharSymbol { line: usize, column: usize, text: String },
+
+    /// A movement is not one of `L`, `R` or `N`.
+    InvalidMovement { line: usize, column: usize, text: String },
+
+    /// A state is defined more than once.
+    DuplicateState { line: usize, column: usize, text: String },
+
+    /// A transition refers to a state that is never defined.
+    UnknownState { line: usize, column: usize, text: String },
+
+    /// A state row doesn't have one entry per symbol in the header.
+    RowLength { line: usize, column: usize, text: String,
+                expected: usize, found: usize },
+
+    /// A line starts with `!`, but isn't one of the known directives.
+    UnknownDirective { line: usize, column: usize, text: String },
+
+    /// The key of an entry in a keyed row is not one of the input symbols.
+    UnknownSymbol { line: usize, column: usize, text: String },
+
+    /// A keyed row has more than one entry for a symbol, or more than one
+    /// `*:` entry.
+    DuplicateEntry { line: usize, column: usize, text: String },
+
+    /// A state is declared by two different directives, like `!accept` and
+    /// `!reject`. `previous` is the first one.
+    ConflictingDirective { line: usize, column: usize, text: String,
+                           previous: &'static str },
+
+    /// A symbol or state is called `=`, which stands for the same symbol or
+    /// state in transitions.
+    ReservedName { line: usize, column: usize, text: String },
+
+    /// A machine in another format can't be read. `line` and `column` are 0
+    /// if the problem is not at a particular place in the source.
+    Malformed { format: Format, line: usize, column: usize, message: String },
+}
+
+/// A running turing machine crashed. This records where and when it happened.
+#[derive(Debug, Clone)]
+pub struct RunError {
+    pub kind: RunErrorKind,
+    /// The name of the current state
+    pub state: String,
+    /// The head position
+    pub head: isize,
+    /// The symbol under the head
+    pub symbol: char,
+    /// The number of steps that were successfully executed before the crash
+    pub step: u64
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RunErrorKind {
+    /// The symbol under the head is not one of the input symbols.
+    UnknownSymbol,
+    /// The transition leads to a state that doesn't exist.
+    UnknownState(String)
+}
+
+/// A `TMDesc` can't be turned into a `DenseTable`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TableError {
+    /// There are more symbols than fit into the table (the number is given).
+    TooManySymbols(usize),
+    /// There are more states than fit into the table (the number is given).
+    TooManyStates(usize),
+    /// The machine is broken, as described by the diagnostic.
+    Invalid(Diagnostic)
+}
+
+/// A `TMDesc` can't be written in a format, because the format can't
+/// describe the machine.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExportError {
+    pub format: Format,
+    /// What the format lacks
+    pub reason: String
+}
+
+/// A `Configuration` can't be loaded or saved, or doesn't fit the machine.
+#[derive(Debug)]
+pub enum ConfigurationError {
+    Io(io::Error),
+    /// The file isn't valid JSON.
+    Syntax(ParserError),
+    /// A field is missing or has the wrong type (the name is given).
+    BadField(&'static str),
+    /// The machine doesn't have the state of the configuration.
+    UnknownState(String),
+    /// The head is too far away from the tape (its position is given).
+    HeadOutOfRange(isize),
+    /// The tape doesn't reach position 0 (its start is given).
+    TapeOutOfRange(isize),
+    /// The machine can't be run at all.
+    Invalid(Diagnostic),
+    /// The machine can't be run by `FastTM` or `MacroTM`.
+    Table(TableError)
 }
 
 impl From<io::Error> for TMDescError {
@@ -17,7 +131,103 @@ impl From<io::Error> for TMDescError {
 impl Display for TMDescError {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
         match self {
-            &Io(ref e) => write!(f, "I/O error: {}", e)
+            &Io(ref e) => write!(f, "I/O error: {}", e),
+            &BadTransitionArity { line, column, ref text } =>
+                write!(f, "{}:{}: transition `{}' should look like \
+                       state,symbol,movement", line, column, text),
+            &MultiCharSymbol { line, column, ref text } =>
+                write!(f, "{}:{}: symbol `{}' is not a single character",
+                       line, column, text),
+            &InvalidMovement { line, column, ref text } =>
+                write!(f, "{}:{}: `{}' is not a valid movement (L, R or N)",
+                       line, column, text),
+            &DuplicateState { line, column, ref text } =>
+                write!(f, "{}:{}: state `{}' is defined twice",
+                       line, column, text),
+            &UnknownState { line, column, ref text } =>
+                write!(f, "{}:{}: state `{}' is not defined",
+                       line, column, text),
+            &RowLength { line, column, ref text, expected, found } =>
+                write!(f, "{}:{}: state `{}' has {} entries, but there are \
+                       {} symbols", line, column, text, found, expected),
+            &UnknownDirective { line, column, ref text } =>
+                write!(f, "{}:{}: unknown directive `{}'", line, column, text),
+            &UnknownSymbol { line, column, ref text } =>
+                write!(f, "{}:{}: `{}' is not an input symbol", line, column, text),
+            &DuplicateEntry { line, column, ref text } =>
+                write!(f, "{}:{}: the row has more than one entry for `{}'",
+                       line, column, text),
+            &ConflictingDirective { line, column, ref text, previous } =>
+                write!(f, "{}:{}: state `{}' is already declared with {}",
+                       line, column, text, previous),
+            &ReservedName { line, column, ref text } =>
+                write!(f, "{}:{}: `{}' can't be a symbol or state name, because it \
+                       stands for the same one in transitions", line, column, text),
+            &Malformed { format, line: 0, ref message, .. } =>
+                write!(f, "{} (in {} format)", message, format),
+            &Malformed { format, line, column, ref message } =>
+                write!(f, "{}:{}: {} (in {} format)", line, column, message, format),
+        }
+    }
+}
+
+impl From<io::Error> for ConfigurationError {
+    fn from(error: io::Error) -> ConfigurationError {
+        ConfigurationError::Io(error)
+    }
+}
+
+impl From<ParserError> for ConfigurationError {
+    fn from(error: ParserError) -> ConfigurationError {
+        ConfigurationError::Syntax(error)
+    }
+}
+
+impl Display for ConfigurationError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match *self {
+            ConfigurationError::Io(ref e) => write!(f, "I/O error: {}", e),
+            ConfigurationError::Syntax(ref e) => write!(f, "invalid JSON: {}", e),
+            ConfigurationError::BadField(name) =>
+                write!(f, "field `{}' is missing or invalid", name),
+            ConfigurationError::UnknownState(ref name) =>
+                write!(f, "state `{}' is not defined", name),
+            ConfigurationError::HeadOutOfRange(head) =>
+                write!(f, "the head at position {} is not on the tape", head),
+            ConfigurationError::TapeOutOfRange(start) =>
+                write!(f, "the tape starting at position {} doesn't reach position 0", start),
+            ConfigurationError::Invalid(ref diag) => write!(f, "{}", diag),
+            ConfigurationError::Table(ref e) => write!(f, "{}", e)
+        }
+    }
+}
+
+impl Display for RunError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        try!(match self.kind {
+            RunErrorKind::UnknownSymbol =>
+                write!(f, "symbol `{}' is not an input symbol", self.symbol),
+            RunErrorKind::UnknownState(ref name) =>
+                write!(f, "transition on `{}' leads to unknown state `{}'",
+                       self.symbol, name)
+        });
+        write!(f, " (in state {} at position {}, after {} steps)",
+               self.state, self.head, self.step)
+    }
+}
+
+impl Display for ExportError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "the machine can't be written in {} format: {}", self.format, self.reason)
+    }
+}
+
+impl Display for TableError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match *self {
+            TableError::TooManySymbols(n) => write!(f, "too many symbols ({})", n),
+            TableError::TooManyStates(n) => write!(f, "too many states ({})", n),
+            TableError::Invalid(ref diag) => write!(f, "{}", diag)
         }
     }
 }
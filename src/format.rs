@@ -0,0 +1,108 @@
+//! The file formats that machine descriptions can be read from and written
+//! to. The TM format is the native one; the others are for exchanging
+//! machines with other tools.
+
+use std::fmt::{self, Display, Formatter};
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use rustc_serialize::json::ToJson;
+use {TMDesc, TMDescError, ExportError};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    /// The tab-separated format described in lib.rs
+    Tm,
+    /// JSON, see json.rs
+    Json,
+    /// The compact notation of the busy beaver community, see
+    /// busy_beaver.rs
+    BusyBeaver,
+    /// The YAML documents of turingmachine.io, see turingmachine_io.rs
+    Yaml,
+    /// JFLAP's XML files, see jflap.rs
+    Jflap,
+    /// Morphett-style quintuples, see quintuple.rs
+    Quintuple
+}
+
+impl Format {
+    pub fn parse(s: &str) -> Option<Format> {
+        match s {
+            "tm" => Some(Format::Tm),
+            "json" => Some(Format::Json),
+            "bb" => Some(Format::BusyBeaver),
+            "yaml" | "yml" => Some(Format::Yaml),
+            "jff" | "jflap" => Some(Format::Jflap),
+            "quint" | "quintuple" => Some(Format::Quintuple),
+            _ => None
+        }
+    }
+
+    /// Guess the format from the extension of a file name. Files with an
+    /// unknown extension are assumed to be TM files.
+    pub fn from_path(path: &Path) -> Format {
+        path.extension().and_then(|ext| ext.to_str())
+            .and_then(|ext| Format::parse(&ext.to_lowercase()))
+            .unwrap_or(Format::Tm)
+    }
+
+    /// The usual extension of files in this format.
+    pub fn extension(&self) -> &'static str {
+        match *self {
+            Format::Tm => "tm",
+            Format::Json => "json",
+            Format::BusyBeaver => "bb",
+            Format::Yaml => "yaml",
+            Format::Jflap => "jff",
+            Format::Quintuple => "quint"
+        }
+    }
+}
+
+impl Display for Format {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match *self {
+            Format::Tm => write!(f, "TM"),
+            Format::Json => write!(f, "JSON"),
+            Format::BusyBeaver => write!(f, "busy beaver"),
+            Format::Yaml => write!(f, "turingmachine.io YAML"),
+            Format::Jflap => write!(f, "JFLAP"),
+            Format::Quintuple => write!(f, "quintuple")
+        }
+    }
+}
+
+impl TMDesc {
+    pub fn from_string_in(format: Format, string: &str) -> Result<TMDesc, TMDescError> {
+        match format {
+            Format::Tm => TMDesc::from_string(string),
+            Format::Json => TMDesc::from_json_str(string),
+            Format::BusyBeaver => TMDesc::from_busy_beaver(string),
+            Format::Yaml => TMDesc::from_yaml(string),
+            Format::Jflap => TMDesc::from_jflap(string),
+            Format::Quintuple => TMDesc::from_quintuples(string)
+        }
+    }
+
+    pub fn from_file_in(format: Format, path: &Path) -> Result<TMDesc, TMDescError> {
+        let mut file = try!(File::open(path));
+        let mut string = String::new();
+
+        try!(file.read_to_string(&mut string));
+        TMDesc::from_string_in(format, &string)
+    }
+
+    /// Write the description in a format. This fails if the format can't
+    /// describe the machine.
+    pub fn to_string_in(&self, format: Format) -> Result<String, ExportError> {
+        match format {
+            Format::Tm => self.to_tm(),
+            Format::Json => Ok(format!("{}\n", self.to_json().pretty())),
+            Format::BusyBeaver => self.to_busy_beaver().map(|s| format!("{}\n", s)),
+            Format::Yaml => self.to_yaml(),
+            Format::Jflap => self.to_jflap(),
+            Format::Quintuple => self.to_quintuples()
+        }
+    }
+}
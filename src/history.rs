@@ -0,0 +1,212 @@
+//! Going back in time: `TM` can record an undo log of all the steps it
+//! executes, and save checkpoints of the whole machine at regular intervals.
+//!
+//! Single steps are undone with the undo log, which only reaches back to the
+//! last checkpoint that was saved or restored, so it never grows beyond one
+//! checkpoint interval. Jumps to an arbitrary step, and single steps back
+//! past the start of the undo log, restore the closest checkpoint before it
+//! and replay the steps from there, which works in both directions because
+//! turing machines are deterministic.
+
+use std::cmp;
+use {TM, Tape, StepOutcome, RunError};
+
+/// What's needed to undo one step.
+pub struct Undo {
+    /// The state before the step
+    pub state: usize,
+    /// The head position before the step
+    pub head: isize,
+    /// The symbol that was overwritten
+    pub symbol: char
+}
+
+#[derive(Clone)]
+pub struct Checkpoint {
+    steps: u64,
+    state: usize,
+    head: isize,
+    tape: Tape
+}
+
+impl Checkpoint {
+    pub fn of(tm: &TM) -> Checkpoint {
+        Checkpoint {
+            steps: tm.steps,
+            state: tm.state_index,
+            head: tm.head,
+            tape: tm.tape.clone()
+        }
+    }
+}
+
+pub struct History {
+    /// One entry for each step since `undo_start`, the last checkpoint that
+    /// was saved or restored
+    undo: Vec<Undo>,
+    undo_start: u64,
+    /// Sorted by step. The first one is where recording started.
+    checkpoints: Vec<Checkpoint>,
+    interval: u64
+}
+
+impl History {
+    pub fn record(&mut self, undo: Undo) {
+        self.undo.push(undo);
+    }
+
+    pub fn wants_checkpoint(&self, steps: u64) -> bool {
+        steps % self.interval == 0 && self.find_checkpoint(steps).is_err()
+    }
+
+    pub fn add_checkpoint(&mut self, checkpoint: Checkpoint) {
+        // When steps are replayed, their checkpoints may already exist.
+        if let Err(i) = self.find_checkpoint(checkpoint.steps) {
+            // The steps before the new checkpoint can be replayed from the
+            // one before it, so the undo log doesn't need to grow beyond
+            // one interval.
+            self.undo.clear();
+            self.undo_start = checkpoint.steps;
+            self.checkpoints.insert(i, checkpoint);
+        }
+    }
+
+    fn find_checkpoint(&self, steps: u64) -> Result<usize, usize> {
+        self.checkpoints.binary_search_by(|c| c.steps.cmp(&steps))
+    }
+
+    /// The latest checkpoint at or before the given step.
+    fn checkpoint_before(&self, steps: u64) -> Option<&Checkpoint> {
+        match self.find_checkpoint(steps) {
+            Ok(i) => Some(&self.checkpoints[i]),
+            Err(0) => None,
+            Err(i) => Some(&self.checkpoints[i - 1])
+        }
+    }
+
+    fn start(&self) -> u64 {
+        self.checkpoints[0].steps
+    }
+}
+
+impl<'a> TM<'a> {
+    /// Start recording history, so that steps can be undone. A checkpoint is
+    /// saved every `checkpoint_interval` steps; more checkpoints make jumps
+    /// faster, but need more memory.
+    pub fn record_history(&mut self, checkpoint_interval: u64) {
+        assert!(checkpoint_interval > 0);
+        self.history = Some(History {
+            undo: vec![],
+            undo_start: self.steps,
+            checkpoints: vec![Checkpoint::of(self)],
+            interval: checkpoint_interval
+        });
+    }
+
+    /// The earliest step that the machine can go back to, if history is
+    /// being recorded.
+    pub fn history_start(&self) -> Option<u64> {
+        self.history.as_ref().map(|h| h.start())
+    }
+
+    /// Undo the last step. Returns `false` if that's impossible, because no
+    /// history has been recorded.
+    pub fn step_back(&mut self) -> bool {
+        let (start, undo_start) = match self.history {
+            Some(ref h) => (h.start(), h.undo_start),
+            None => return false
+        };
+        if self.steps == start {
+            return false;
+        }
+
+        if self.steps > undo_start {
+            self.undo_step();
+        } else {
+            let target = self.steps - 1;
+            self.restore_checkpoint_before(target);
+            while self.steps < target {
+                // These steps have been executed before, so they can't fail.
+                self.run_step().unwrap();
+            }
+        }
+        true
+    }
+
+    /// Go to the state after `n` steps, either backwards or forwards. The
+    /// machine can't go back further than the start of the history. Going
+    /// forward stops early if the machine halts, gets stuck or crashes.
+    pub fn goto_step(&mut self, n: u64) -> Result<StepOutcome, RunError> {
+        let n = if n < self.steps {
+            cmp::max(n, self.history_start().unwrap_or(self.steps))
+        } else {
+            n
+        };
+
+        if n < self.steps {
+            let (undo_start, checkpoint) = {
+                let h = self.history.as_ref().unwrap();
+                (h.undo_start, h.checkpoint_before(n).unwrap().steps)
+            };
+            // Undo if that's less work than replaying from the checkpoint.
+            if n >= undo_start && self.steps - n <= n - checkpoint {
+                while self.steps > n {
+                    self.undo_step();
+                }
+                return Ok(self.outcome());
+            }
+            self.restore_checkpoint_before(n);
+        } else {
+            let steps = self.steps;
+            let later = self.history.as_ref()
+                .and_then(|h| h.checkpoint_before(n))
+                .map_or(false, |c| c.steps > steps);
+            if later {
+                self.restore_checkpoint_before(n);
+            }
+        }
+
+        while self.steps < n {
+            match try!(self.run_step()) {
+                StepOutcome::Continue => (),
+                outcome => return Ok(outcome)
+            }
+        }
+        Ok(self.outcome())
+    }
+
+    fn undo_step(&mut self) {
+        let undo = self.history.as_mut().unwrap().undo.pop().unwrap();
+        self.state_index = undo.state;
+        self.state = &self.desc.states[undo.state];
+        self.head = undo.head;
+        self.tape[undo.head] = undo.symbol;
+        self.steps -= 1;
+        self.last_step = None;
+    }
+
+    fn restore_checkpoint_before(&mut self, n: u64) {
+        let checkpoint = {
+            let h = self.history.as_mut().unwrap();
+            let checkpoint = h.checkpoint_before(n).unwrap().clone();
+            h.undo.clear();
+            h.undo_start = checkpoint.steps;
+            checkpoint
+        };
+        self.state_index = checkpoint.state;
+        self.state = &self.desc.states[checkpoint.state];
+        self.head = checkpoint.head;
+        self.tape = checkpoint.tape;
+        self.steps = checkpoint.steps;
+        self.last_step = None;
+    }
+
+    /// What `run_step` would return, without executing a step.
+    fn outcome(&self) -> StepOutcome {
+        match self.state.halt {
+            Some(kind) => StepOutcome::Halted(kind),
+            None if self.current_transition().is_none() => StepOutcome::Stuck,
+            None => StepOutcome::Continue
+        }
+    }
+}
@@ -1,49 +1,598 @@
-#![feature(io)]
+//! An interpreter for turing machines.
+#![feature(rustc_private)]
+#![cfg(not(test))]
 
 extern crate turing_machines;
+extern crate getopts;
 
-use turing_machines::{TMDesc, TM};
-use std::io::{Read, BufRead, BufReader};
+use turing_machines::{TMDesc, TM, State, Tape, StepOutcome, HaltKind, RunError, render};
+use turing_machines::{Tracer, TraceFormat, Debugger, FastTM, MacroTM};
+use turing_machines::{LoopDetector, RunOutcome, Configuration, ConfigurationError};
+use turing_machines::RenderStyle;
+use turing_machines::{SpaceTime, DiagramFormat, Format};
+use std::io::{self, Read, Write, BufWriter};
 use std::fs::File;
+use std::process::exit;
 use std::path::Path;
 
+enum Input {
+    Stdin,
+    File(String),
+    String(String)
+}
+
+impl Input {
+    fn read(&self) -> io::Result<String> {
+        let mut string = String::new();
+        match *self {
+            Input::Stdin => {
+                try!(io::stdin().read_to_string(&mut string));
+            }
+            Input::File(ref path) => {
+                let mut file = try!(File::open(&Path::new(path)));
+                try!(file.read_to_string(&mut string));
+            }
+            Input::String(ref s) => string.push_str(s)
+        }
+
+        // Whitespace is never part of the input, so that input files can end
+        // with a newline.
+        Ok(string.chars().filter(|c| !c.is_whitespace()).collect())
+    }
+}
+
+enum Output {
+    /// The tape from the head to the right end, like libturingrt does
+    Head,
+    /// The whole tape, without leading and trailing blanks
+    Tape,
+    /// The name of the final state
+    State,
+    /// The whole tape, with the final state in front of the head
+    Configuration
+}
+
+impl Output {
+    fn parse(arg: Option<&str>) -> Output {
+        use Output::*;
+
+        match arg {
+            None => Head,
+            Some("head") => Head,
+            Some("tape") => Tape,
+            Some("state") => State,
+            Some("config") => Configuration,
+            Some(arg) => {
+                println!("Invalid argument {} to --output\n", arg);
+                exit(1);
+            }
+        }
+    }
+}
+
+struct Config {
+    machine: String,
+    format: Format,
+    input: Input,
+    max_steps: Option<u64>,
+    output: Output,
+    trace: Option<TraceFormat>,
+    trace_window: usize,
+    debug: bool,
+    block_size: Option<usize>,
+    detect_loops: bool,
+    resume: Option<String>,
+    save: Option<String>,
+    style: RenderStyle,
+    diagram: Option<(String, DiagramFormat)>,
+    diagram_every: u64,
+    diagram_states: bool,
+}
+
+fn get_config() -> Config {
+    use getopts::*;
+
+    let options = &[
+        optflag("h", "help", "Print a help message"),
+        optopt("", "format",
+                "Read the machine in the TM format (tm), as JSON (json), in the \
+                busy beaver notation (bb), as a turingmachine.io document \
+                (yaml), as a JFLAP file (jff), or as quintuples (quint). By \
+                default, the format is taken from the file extension",
+                "tm|json|bb|yaml|jff|quint"),
+        optopt("i", "input", "Use STRING as the input, instead of reading stdin",
+                "STRING"),
+        optopt("f", "input-file", "Read the input from FILE, instead of stdin",
+                "FILE"),
+        optopt("", "max-steps", "Give up after N steps", "N"),
+        optopt("", "output",
+                "Print the tape from the head to the right (head, default), \
+                the whole tape (tape), the final state (state), or the whole \
+                tape with the state in front of the head (config)",
+                "head|tape|state|config"),
+        optopt("", "trace",
+                "Write every step to stderr, as text (human) or as one JSON \
+                object per line (json)",
+                "human|json"),
+        optopt("", "trace-window",
+                "Show N cells to each side of the head in the trace (default: 10)",
+                "N"),
+        optflag("d", "debug",
+                "Run the machine in an interactive debugger, which reads commands \
+                from stdin"),
+        optopt("", "block-size",
+                "Simulate with macro steps on blocks of N cells, which is much \
+                faster for machines that run for a very long time",
+                "N"),
+        optflag("", "detect-loops",
+                "Stop machines that provably never halt, because they repeat a \
+                configuration, or repeat themselves further out on the tape"),
+        optopt("", "resume",
+                "Continue from the configuration in FILE, instead of starting \
+                with an input",
+                "FILE"),
+        optopt("", "save",
+                "Write the configuration to FILE when the machine stops, so \
+                that the run can be continued with --resume",
+                "FILE"),
+        optflag("", "color",
+                "Highlight the head and the state with ANSI escape sequences"),
+        optopt("", "diagram",
+                "Draw a space-time diagram of the run, with one row per step, \
+                into FILE. The format (PBM, PPM or SVG) is taken from the file \
+                extension",
+                "FILE"),
+        optopt("", "diagram-every", "Only draw every Nth step in the diagram", "N"),
+        optflag("", "diagram-states", "Color the head by the state in the diagram"),
+    ];
+
+    let mut args = std::env::args();
+    let program = args.next().unwrap();
+    let args_vec = args.collect::<Vec<_>>();
+
+    let matches = getopts(&args_vec, options).unwrap();
+
+    let brief = format!("Usage: {} [options] machine.tm", program);
+    if matches.opt_present("help") {
+        print!("{}", usage(&brief, options));
+        exit(0);
+    }
+
+    if matches.free.len() != 1 {
+        println!("{}", brief);
+        exit(1);
+    }
+
+    let format = matches.opt_str("format").map_or_else(|| {
+        Format::from_path(Path::new(&matches.free[0]))
+    }, |arg| {
+        Format::parse(&arg).unwrap_or_else(|| {
+            println!("Invalid argument {} to --format\n", arg);
+            exit(1);
+        })
+    });
+
+    let input = match (matches.opt_str("input"), matches.opt_str("input-file")) {
+        (None, None) => Input::Stdin,
+        (Some(s), None) => Input::String(s),
+        (None, Some(path)) => Input::File(path),
+        (Some(_), Some(_)) => {
+            println!("--input and --input-file can't be used together");
+            exit(1);
+        }
+    };
+
+    let resume = matches.opt_str("resume");
+    if resume.is_some() {
+        if let Input::Stdin = input {} else {
+            println!("--resume can't be used with --input or --input-file");
+            exit(1);
+        }
+    }
+
+    let debug = matches.opt_present("debug");
+    if debug && resume.is_none() {
+        if let Input::Stdin = input {
+            println!("--debug needs --input, --input-file or --resume");
+            exit(1);
+        }
+    }
+
+    let max_steps = matches.opt_str("max-steps").map(|arg| {
+        arg.parse().unwrap_or_else(|_| {
+            println!("Invalid argument {} to --max-steps\n", arg);
+            exit(1);
+        })
+    });
+
+    let output = matches.opt_str("output");
+
+    let trace = matches.opt_str("trace").map(|arg| {
+        TraceFormat::parse(&arg).unwrap_or_else(|| {
+            println!("Invalid argument {} to --trace\n", arg);
+            exit(1);
+        })
+    });
+
+    let trace_window = matches.opt_str("trace-window").map_or(10, |arg| {
+        arg.parse().unwrap_or_else(|_| {
+            println!("Invalid argument {} to --trace-window\n", arg);
+            exit(1);
+        })
+    });
+
+    let block_size = matches.opt_str("block-size").map(|arg| {
+        match arg.parse() {
+            Ok(n) if n > 0 => n,
+            _ => {
+                println!("Invalid argument {} to --block-size\n", arg);
+                exit(1);
+            }
+        }
+    });
+    if block_size.is_some() && (debug || trace.is_some()) {
+        println!("--block-size can't be used with --debug or --trace");
+        exit(1);
+    }
+
+    let detect_loops = matches.opt_present("detect-loops");
+    if detect_loops && (debug || block_size.is_some()) {
+        println!("--detect-loops can't be used with --debug or --block-size");
+        exit(1);
+    }
+
+    let diagram = matches.opt_str("diagram").map(|path| {
+        let format = Path::new(&path).extension().and_then(|ext| ext.to_str())
+            .and_then(|ext| DiagramFormat::parse(&ext.to_lowercase()));
+        match format {
+            Some(format) => (path, format),
+            None => {
+                println!("The diagram file name must end with .pbm, .ppm or .svg");
+                exit(1);
+            }
+        }
+    });
+    if diagram.is_some() && (debug || block_size.is_some()) {
+        println!("--diagram can't be used with --debug or --block-size");
+        exit(1);
+    }
+
+    let diagram_every = matches.opt_str("diagram-every").map_or(1, |arg| {
+        match arg.parse() {
+            Ok(n) if n > 0 => n,
+            _ => {
+                println!("Invalid argument {} to --diagram-every\n", arg);
+                exit(1);
+            }
+        }
+    });
+
+    Config {
+        machine: matches.free[0].clone(),
+        format: format,
+        input: input,
+        max_steps: max_steps,
+        output: Output::parse(output.as_ref().map(|s|&s[..])),
+        trace: trace,
+        trace_window: trace_window,
+        debug: debug,
+        block_size: block_size,
+        detect_loops: detect_loops,
+        resume: resume,
+        save: matches.opt_str("save"),
+        style: if matches.opt_present("color") { RenderStyle::Ansi } else { RenderStyle::Plain },
+        diagram: diagram,
+        diagram_every: diagram_every,
+        diagram_states: matches.opt_present("diagram-states"),
+    }
+}
+
+/// Where a machine ended up, no matter how it was run.
+struct Finish<'a> {
+    /// `Continue` if the step limit was reached
+    result: Result<StepOutcome, RunError>,
+    state: &'a State,
+    head: isize,
+    /// The symbol under the head
+    symbol: char,
+    /// `None` if the tape isn't needed, because it may be too big to copy
+    tape: Option<Tape>,
+    steps: u64,
+    /// Why the machine never halts, if that is known
+    never_halts: Option<String>
+}
+
+impl<'a> Finish<'a> {
+    fn from_tm(tm: TM<'a>, result: Result<RunOutcome, RunError>) -> Finish<'a> {
+        let mut never_halts = None;
+        let result = result.map(|outcome| match outcome {
+            RunOutcome::Halted(kind) => StepOutcome::Halted(kind),
+            RunOutcome::Stuck => StepOutcome::Stuck,
+            RunOutcome::LimitReached => StepOutcome::Continue,
+            RunOutcome::NonHalting { reason, period } => {
+                never_halts = Some(format!("{} with period {}", reason, period));
+                StepOutcome::Continue
+            }
+        });
+        Finish {
+            result: result,
+            state: tm.state(),
+            head: tm.head(),
+            symbol: tm.tape().get(tm.head()),
+            tape: Some(tm.tape().clone()),
+            steps: tm.steps(),
+            never_halts: never_halts
+        }
+    }
+
+    fn from_fast_tm(tm: FastTM<'a>, result: Result<StepOutcome, RunError>) -> Finish<'a> {
+        let tape = tm.tape();
+        Finish {
+            result: result,
+            state: tm.state(),
+            head: tm.head(),
+            symbol: tape.get(tm.head()),
+            tape: Some(tape),
+            steps: tm.steps(),
+            never_halts: None
+        }
+    }
+
+    /// The tape of a `MacroTM` is only expanded if `with_tape` is set, since
+    /// it can be huge after a long run.
+    fn from_macro_tm(tm: MacroTM<'a>, result: Result<StepOutcome, RunError>, with_tape: bool)
+            -> Finish<'a> {
+        Finish {
+            result: result,
+            state: tm.state(),
+            head: tm.head(),
+            symbol: tm.symbol(),
+            tape: if with_tape { Some(tm.tape()) } else { None },
+            steps: tm.steps(),
+            never_halts: if tm.never_halts() {
+                Some("endless sweep or loop within a block".into())
+            } else {
+                None
+            }
+        }
+    }
+
+    fn tape(&self) -> &Tape {
+        self.tape.as_ref().expect("the tape wasn't kept")
+    }
+}
+
+/// Run the machine until it stops, until `max_steps` steps have been
+/// executed, or until the detector (if any) finds that it never halts.
+fn run<W: Write>(tm: &mut TM, max_steps: Option<u64>, tracer: &mut Option<Tracer<W>>,
+                 detector: &mut Option<LoopDetector>, diagram: &mut Option<SpaceTime>)
+        -> Result<RunOutcome, RunError> {
+    if let Some(ref mut diagram) = *diagram {
+        diagram.record(tm);
+    }
+    loop {
+        if max_steps.map_or(false, |max| tm.steps() >= max) {
+            return Ok(RunOutcome::LimitReached);
+        }
+        let outcome = try!(tm.run_step());
+        if let Some(ref mut tracer) = *tracer {
+            if let Err(e) = tracer.trace(tm) {
+                println_stderr(&format!("Failed to write the trace: {}", e));
+                exit(1);
+            }
+        }
+        if let Some(ref mut diagram) = *diagram {
+            if tm.last_step().is_some() {
+                diagram.record(tm);
+            }
+        }
+        match outcome {
+            StepOutcome::Halted(kind) => return Ok(RunOutcome::Halted(kind)),
+            StepOutcome::Stuck => return Ok(RunOutcome::Stuck),
+            StepOutcome::Continue => ()
+        }
+        if let Some(ref mut detector) = *detector {
+            if let Some((reason, period)) = detector.observe(tm) {
+                return Ok(RunOutcome::NonHalting { reason: reason, period: period });
+            }
+        }
+    }
+}
+
+fn write_diagram(diagram: &SpaceTime, desc: &TMDesc, path: &str, format: DiagramFormat) {
+    let result = File::create(&Path::new(path)).and_then(|file| {
+        let mut out = BufWriter::new(file);
+        diagram.write(desc, format, &mut out).and_then(|_| out.flush())
+    });
+    if let Err(e) = result {
+        println_stderr(&format!("Failed to write the diagram to `{}`: {}", path, e));
+    }
+}
+
+/// The machine at the start of the run.
+fn start<'a>(desc: &'a TMDesc, input: &str, resume: &Option<Configuration>) -> TM<'a> {
+    match *resume {
+        Some(ref configuration) => {
+            TM::from_configuration(desc, configuration).unwrap_or_else(|e| {
+                println!("Failed to resume. Reason: {}", e);
+                exit(1);
+            })
+        }
+        None => TM::new(desc, input).unwrap_or_else(|e| {
+            println!("Failed to start the machine. Reason: {}", e);
+            exit(1);
+        })
+    }
+}
+
+/// A resumed `FastTM` or `MacroTM`, or `None` if the machine can't be run
+/// that way.
+fn resumed<T>(result: Result<T, ConfigurationError>) -> Option<T> {
+    match result {
+        Ok(tm) => Some(tm),
+        Err(ConfigurationError::Table(_)) => None,
+        Err(e) => {
+            println!("Failed to resume. Reason: {}", e);
+            exit(1);
+        }
+    }
+}
+
 fn main() {
-    let file = File::open(&Path::new("../jn.tm"))
-        .unwrap_or_else(|e| panic!("can't open file: {}", e));
-    let reader = BufReader::new(file);
+    let config = get_config();
+    let desc = match TMDesc::from_file_in(config.format, &Path::new(&config.machine)) {
+        Ok(desc) => desc,
+        Err(e) => {
+            println!("Failed to load `{}`. Reason: {}", config.machine, e);
+            exit(1);
+        }
+    };
 
-    let mut tmd = TMDesc::new();
+    // A run starts either with an input, or from a saved configuration.
+    let (input, resume) = match config.resume {
+        Some(ref path) => match Configuration::from_file(&Path::new(path)) {
+            Ok(configuration) => (String::new(), Some(configuration)),
+            Err(e) => {
+                println!("Failed to load `{}`. Reason: {}", path, e);
+                exit(1);
+            }
+        },
+        None => {
+            let input = config.input.read().unwrap_or_else(|e| {
+                println!("Failed to read the input. Reason: {}", e);
+                exit(1);
+            });
+            (input, None)
+        }
+    };
 
-    for line_res in reader.lines() {
-        let line = line_res.unwrap();
-        if let Some(words) = TMDesc::parse_line(line.trim_right()) {
-            tmd.handle_line(&words);
+    if config.debug {
+        let stdin = io::stdin();
+        let mut debugger = Debugger::new(start(&desc, &input, &resume));
+        debugger.set_style(config.style);
+        if let Err(e) = debugger.repl(stdin.lock(), &mut io::stdout()) {
+            println_stderr(&format!("{}", e));
+            exit(1);
         }
+        return;
     }
 
-    tmd.resolve_all_state_indices();
+    // Only the state is printed with `--output state`, so a huge tape
+    // doesn't have to be expanded then.
+    let needs_tape = match config.output {
+        Output::State => config.save.is_some(),
+        _ => true
+    };
 
-    //println!("The TM is now configured as follows:");
-    //println!("");
-    //println!("{}", json::encode(tm));
-    //println!("{}", tmd);
+    // Without a trace or loop detection, the machine is run by the faster
+    // FastTM, or by MacroTM if a block size is given, unless they can't
+    // handle the machine.
+    let finish = match (config.block_size, config.trace) {
+        _ if config.detect_loops || config.diagram.is_some() => None,
+        (Some(block_size), _) => match resume {
+            Some(ref configuration) =>
+                resumed(MacroTM::from_configuration(&desc, configuration, block_size)),
+            None => MacroTM::new(&desc, &input, block_size).ok()
+        }.map(|mut tm| {
+            let result = tm.run(config.max_steps);
+            Finish::from_macro_tm(tm, result, needs_tape)
+        }),
+        (None, None) => match resume {
+            Some(ref configuration) => resumed(FastTM::from_configuration(&desc, configuration)),
+            None => FastTM::new(&desc, &input).ok()
+        }.map(|mut tm| {
+            let result = tm.run(config.max_steps);
+            Finish::from_fast_tm(tm, result)
+        }),
+        (None, Some(_)) => None
+    };
 
-    let input_file = File::open(&Path::new("../big-troll.txt"))
-        .unwrap_or_else(|e| panic!("can't open input: {}", e));
-    let input_reader = BufReader::new(input_file);
+    let finish = match finish {
+        Some(finish) => finish,
+        None => {
+            let mut tm = start(&desc, &input, &resume);
+            let mut tracer = config.trace.map(|format| {
+                let mut tracer = Tracer::new(BufWriter::new(io::stderr()), format,
+                                             config.trace_window);
+                tracer.set_style(config.style);
+                tracer
+            });
+            let mut detector = if config.detect_loops {
+                Some(LoopDetector::new(&tm))
+            } else {
+                None
+            };
+            let mut diagram = config.diagram.as_ref().map(|_| {
+                SpaceTime::new(config.diagram_every, config.diagram_states)
+            });
+            let result = run(&mut tm, config.max_steps, &mut tracer, &mut detector,
+                             &mut diagram);
 
-    let filtered: String =
-        input_reader.chars().map(|c| c.unwrap())
-        .filter(|ch| !ch.is_whitespace()).collect();
-    let mut tm = TM::new(&tmd, &filtered);
+            // Make sure the trace comes before everything else.
+            if let Some(tracer) = tracer {
+                let _ = tracer.into_inner().flush();
+            }
+            if let (Some(diagram), Some(&(ref path, format))) = (diagram, config.diagram.as_ref()) {
+                write_diagram(&diagram, &desc, path, format);
+            }
+            Finish::from_tm(tm, result)
+        }
+    };
+
+    if let Some(ref path) = config.save {
+        let configuration = Configuration::new(&finish.state.name, finish.head,
+                                               finish.tape(), finish.steps);
+        if let Err(e) = configuration.to_file(&Path::new(path)) {
+            println_stderr(&format!("Failed to save the configuration to `{}`: {}", path, e));
+        }
+    }
+
+    // The exit statuses are the same as those of machines compiled with
+    // turingc.
+    let status = match finish.result {
+        Ok(StepOutcome::Halted(kind)) => {
+            match kind {
+                HaltKind::Accept =>
+                    println_stderr(&format!("accepted in state {}", finish.state.name)),
+                HaltKind::Reject =>
+                    println_stderr(&format!("rejected in state {}", finish.state.name)),
+                HaltKind::Halt => ()
+            }
+            if kind == HaltKind::Reject { 1 } else { 0 }
+        }
+        Ok(StepOutcome::Stuck) => {
+            println_stderr(&format!("No transition from {} on symbol '{}'",
+                                    finish.state.name, finish.symbol));
+            2
+        }
+        Ok(StepOutcome::Continue) if finish.never_halts.is_some() => {
+            println_stderr(&format!("The machine never halts ({}), found after {} steps",
+                                    finish.never_halts.as_ref().unwrap(), finish.steps));
+            3
+        }
+        Ok(StepOutcome::Continue) => {
+            println_stderr(&format!("Gave up after {} steps", finish.steps));
+            3
+        }
+        Err(ref e) => {
+            println_stderr(&format!("{}", e));
+            2
+        }
+    };
 
-    let mut steps: u64 = 0;
-    while !tm.has_finished() {
-        //println!("{}, {} ≤ {} < {}: {}", tm.state.name, tm.tape.min(), tm.head, tm.tape.max(), tm.tape.to_string());
-        tm.run_step();
-        steps += 1;
+    match config.output {
+        Output::Head =>
+            println!("{}", finish.tape().window(finish.head, finish.tape().max())),
+        Output::Tape => println!("{}", finish.tape().to_trimmed_string()),
+        Output::State => println!("{}", finish.state.name),
+        Output::Configuration =>
+            println!("{}", render(finish.tape(), finish.head, &finish.state.name, None,
+                                  config.style)),
     }
 
-    println!("Outpoot: {}", tm.get_tape_output());
-    println!("Has finished? {} in {} steps!", tm.has_finished(), steps);
+    exit(status);
+}
+
+fn println_stderr(msg: &str) {
+    let _ = writeln!(&mut io::stderr(), "turing: {}", msg);
 }
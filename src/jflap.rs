@@ -0,0 +1,224 @@
+//! JFLAP's `.jff` files for single-tape Turing machines.
+//!
+//! States have numeric ids and names, and may be marked as initial or final.
+//! Transitions refer to states by id, and have a symbol to read and to write
+//! (an empty one is the blank, which JFLAP shows as `□`), and a movement
+//! (`L`, `R` or `S` for staying). Machines halt when there is no transition,
+//! and accept if they reach a final state, so final states are accepting
+//! states in a `TMDesc`. Otherwise they reject: other states without any
+//! transitions are rejecting states, and the remaining states move to a
+//! rejecting state on the symbols they have no transition for (a new one
+//! called `reject`, if there is none). The blank is `□`.
+
+use xml::{self, Element};
+use {TMDesc, TMDescError, ExportError, State, Transition, Movement, HaltKind, Format};
+
+/// The blank symbol
+const BLANK: char = '□';
+
+fn error(line: usize, column: usize, message: String) -> TMDescError {
+    TMDescError::Malformed { format: Format::Jflap, line: line, column: column, message: message }
+}
+
+/// The text of a child element, without surrounding whitespace.
+fn child_text<'a>(element: &'a Element, name: &str) -> Result<&'a str, TMDescError> {
+    match element.child(name) {
+        Some(child) => Ok(child.text.trim()),
+        None => Err(error(element.line, element.column,
+                          format!("<{}> is missing <{}>", element.name, name)))
+    }
+}
+
+/// The symbol of a `<read>` or `<write>`, where an empty one is the blank.
+fn symbol(element: &Element, name: &str) -> Result<char, TMDescError> {
+    let text = try!(child_text(element, name));
+    let mut chars = text.chars();
+    match (chars.next(), chars.next()) {
+        (None, _) => Ok(BLANK),
+        (Some(c), None) => Ok(c),
+        _ => Err(error(element.line, element.column,
+                       format!("symbol `{}' is not a single character", text)))
+    }
+}
+
+impl TMDesc {
+    pub fn from_jflap(s: &str) -> Result<TMDesc, TMDescError> {
+        let root = try!(xml::parse(s).map_err(|e| error(e.line, e.column, e.message)));
+        if root.name != "structure" {
+            return Err(error(root.line, root.column, "expected <structure>".into()));
+        }
+        let kind = try!(child_text(&root, "type"));
+        if kind != "turing" {
+            return Err(error(root.line, root.column,
+                             format!("the automaton is of type `{}', not a Turing machine",
+                                     kind)));
+        }
+        if let Some(tapes) = root.child("tapes") {
+            if tapes.text.trim() != "1" {
+                return Err(error(tapes.line, tapes.column,
+                                 "only machines with one tape are supported".into()));
+            }
+        }
+        // Older versions of JFLAP don't have <automaton>.
+        let automaton = root.child("automaton").unwrap_or(&root);
+
+        let mut desc = TMDesc::new();
+        // The id of each state
+        let mut ids = vec![];
+        let mut initial = None;
+        for element in automaton.children.iter() {
+            if element.name == "block" {
+                return Err(error(element.line, element.column,
+                                 "building blocks are not supported".into()));
+            }
+            if element.name != "state" {
+                continue;
+            }
+            let id = match element.attribute("id") {
+                Some(id) => id,
+                None => return Err(error(element.line, element.column,
+                                         "the state has no id".into()))
+            };
+            let name = element.attribute("name").map_or_else(|| format!("q{}", id), |n| n.into());
+            if ids.contains(&id) || desc.find_state(&name).is_some() {
+                return Err(error(element.line, element.column,
+                                 format!("state `{}' is defined twice", name)));
+            }
+            if element.child("initial").is_some() {
+                initial = Some(ids.len());
+            }
+
+            let mut state = State::empty(name, 0);
+            state.halt = element.child("final").map(|_| HaltKind::Accept);
+            desc.states.push(state);
+            ids.push(id);
+        }
+
+        // The transitions are collected first, because the symbols are only
+        // known at the end.
+        let mut transitions = vec![];
+        for element in automaton.children.iter().filter(|e| e.name == "transition") {
+            let find = |name: &str| -> Result<usize, TMDescError> {
+                let id = try!(child_text(element, name));
+                ids.iter().position(|&i| i == id).ok_or_else(|| {
+                    error(element.line, element.column, format!("there is no state {}", id))
+                })
+            };
+            let from = try!(find("from"));
+            let to = try!(find("to"));
+            let read = try!(symbol(element, "read"));
+            let write = try!(symbol(element, "write"));
+            let movement = match try!(child_text(element, "move")) {
+                "L" => Movement::Left,
+                "R" => Movement::Right,
+                "S" => Movement::None,
+                text => return Err(error(element.line, element.column,
+                                         format!("`{}' is not a valid movement (L, R or S)",
+                                                 text)))
+            };
+            if transitions.iter().any(|&(f, r, _)| f == from && r == read) {
+                return Err(error(element.line, element.column,
+                                 format!("state `{}' has two transitions for `{}'",
+                                         desc.states[from].name, read)));
+            }
+            for &c in [read, write].iter() {
+                if c != BLANK && !desc.input_symbols.contains(&c) {
+                    desc.input_symbols.push(c);
+                }
+            }
+            transitions.push((from, read, Transition {
+                state: desc.states[to].name.clone(),
+                state_index: None,
+                symbol: write,
+                movement: movement
+            }));
+        }
+        desc.input_symbols.push(BLANK);
+
+        let n = desc.input_symbols.len();
+        for state in desc.states.iter_mut() {
+            state.transitions = vec![None; n];
+        }
+        for (from, read, trans) in transitions {
+            let index = desc.input_symbols.iter().position(|&c| c == read).unwrap();
+            desc.states[from].transitions[index] = Some(trans);
+        }
+        for state in desc.states.iter_mut().filter(|s| !s.is_final()) {
+            if state.transitions.iter().all(|t| t.is_none()) {
+                state.halt = Some(HaltKind::Reject);
+            }
+        }
+        desc.stop_when_stuck(HaltKind::Reject, "reject");
+
+        // The initial state is always the first one.
+        match initial {
+            Some(i) => {
+                let state = desc.states.remove(i);
+                desc.states.insert(0, state);
+            }
+            None => return Err(error(0, 0, "there is no initial state".into()))
+        }
+        Ok(desc)
+    }
+
+    /// Write the machine as a JFLAP file, with the states laid out in a grid.
+    /// Halting and accepting states are final states in JFLAP, and the
+    /// transitions of all final states are left out, so that JFLAP also
+    /// rejects in rejecting states. Transitions that just reject are left
+    /// out for the same reason.
+    ///
+    /// This fails if a transition leads to a state that doesn't exist.
+    pub fn to_jflap(&self) -> Result<String, ExportError> {
+        let mut jff = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"no\"?>\n\
+                                    <structure>\n\t<type>turing</type>\n\t<automaton>\n");
+        for (i, state) in self.states.iter().enumerate() {
+            jff.push_str(&format!("\t\t<state id=\"{}\" name=\"{}\">\n",
+                                  i, xml::escape(&state.name)));
+            jff.push_str(&format!("\t\t\t<x>{}.0</x>\n\t\t\t<y>{}.0</y>\n",
+                                  100 + 150 * (i % 5), 100 + 150 * (i / 5)));
+            if i == 0 {
+                jff.push_str("\t\t\t<initial/>\n");
+            }
+            if state.halt == Some(HaltKind::Halt) || state.halt == Some(HaltKind::Accept) {
+                jff.push_str("\t\t\t<final/>\n");
+            }
+            jff.push_str("\t\t</state>\n");
+        }
+
+        let blank = self.input_symbols.last().cloned();
+        // An empty element for the blank
+        let symbol = |name: &str, c: char| if Some(c) == blank {
+            format!("\t\t\t<{}/>\n", name)
+        } else {
+            format!("\t\t\t<{}>{}</{}>\n", name, xml::escape(&c.to_string()), name)
+        };
+        for (i, state) in self.states.iter().enumerate().filter(|&(_, s)| !s.is_final()) {
+            let pairs = state.transitions.iter().zip(self.input_symbols.iter());
+            for (trans, &read) in pairs {
+                let trans = match *trans {
+                    Some(ref trans) if !self.only_stops(trans, read, HaltKind::Reject) => trans,
+                    _ => continue
+                };
+                let to = match self.find_state(&trans.state) {
+                    Some(to) => to,
+                    None => return Err(ExportError {
+                        format: Format::Jflap,
+                        reason: format!("state `{}' is not defined", trans.state)
+                    })
+                };
+                let movement = match trans.movement {
+                    Movement::Left => 'L',
+                    Movement::Right => 'R',
+                    Movement::None => 'S'
+                };
+                jff.push_str(&format!("\t\t<transition>\n\t\t\t<from>{}</from>\n\t\t\t\
+                                       <to>{}</to>\n", i, to));
+                jff.push_str(&symbol("read", read));
+                jff.push_str(&symbol("write", trans.symbol));
+                jff.push_str(&format!("\t\t\t<move>{}</move>\n\t\t</transition>\n", movement));
+            }
+        }
+        jff.push_str("\t</automaton>\n</structure>\n");
+        Ok(jff)
+    }
+}
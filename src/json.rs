@@ -0,0 +1,247 @@
+//! Reading and writing machine descriptions as JSON, for tools that don't
+//! want to deal with the TM format. A description looks like this:
+//!
+//! ```text
+//! {
+//!   "alphabet": ["1", "B"],
+//!   "blank": "B",
+//!   "initial": "even",
+//!   "halting": {"accept": ["ACCEPT"], "reject": ["REJECT"]},
+//!   "states": [
+//!     {"name": "even", "transitions": {
+//!       "1": {"next": "odd", "write": "1", "move": "R"},
+//!       "B": {"next": "ACCEPT", "write": "B", "move": "N"}
+//!     }},
+//!     ...
+//!   ]
+//! }
+//! ```
+//!
+//! - `alphabet` lists the symbols, each as a string of one character.
+//! - `blank` is one of them. It defaults to the last one.
+//! - `states` lists the states in order. A state without `transitions` has
+//!   none; otherwise `transitions` maps symbols to transitions, and symbols
+//!   that are left out have no transition. `move` is `L`, `R` or `N`.
+//! - `initial` is the name of the start state. It defaults to the first one.
+//! - `halting` lists the final states by kind (`halt`, `accept` or
+//!   `reject`). States that are listed only here are added at the end, like
+//!   with the `!halt` directive. There are no final states if it is missing.
+//!
+//! Descriptions are always written with all fields, with the blank last and
+//! the initial state first, as `TMDesc` has them.
+
+use std::collections::BTreeMap;
+use rustc_serialize::json::{self, Json, ToJson, ParserError};
+use {TMDesc, TMDescError, State, Transition, Movement, HaltKind, Format};
+
+fn invalid(message: String) -> TMDescError {
+    TMDescError::Malformed {
+        format: Format::Json, line: 0, column: 0, message: message
+    }
+}
+
+fn field<'a>(obj: &'a Json, path: &str, name: &str) -> Result<&'a Json, TMDescError> {
+    obj.find(name).ok_or_else(|| invalid(format!("{}.{} is missing", path, name)))
+}
+
+fn string<'a>(json: &'a Json, path: &str) -> Result<&'a str, TMDescError> {
+    json.as_string().ok_or_else(|| invalid(format!("{} is not a string", path)))
+}
+
+fn symbol(json: &Json, path: &str) -> Result<char, TMDescError> {
+    let s = try!(string(json, path));
+    if s.chars().count() != 1 {
+        return Err(invalid(format!("{} `{}' is not a single character", path, s)));
+    }
+    Ok(s.chars().next().unwrap())
+}
+
+fn array<'a>(json: &'a Json, path: &str) -> Result<&'a Vec<Json>, TMDescError> {
+    json.as_array().ok_or_else(|| invalid(format!("{} is not an array", path)))
+}
+
+fn object<'a>(json: &'a Json, path: &str) -> Result<&'a BTreeMap<String, Json>, TMDescError> {
+    json.as_object().ok_or_else(|| invalid(format!("{} is not an object", path)))
+}
+
+fn transition(json: &Json, path: &str) -> Result<Transition, TMDescError> {
+    try!(object(json, path));
+    let next = try!(string(try!(field(json, path, "next")), &format!("{}.next", path)));
+    let write = try!(symbol(try!(field(json, path, "write")), &format!("{}.write", path)));
+    let text = try!(string(try!(field(json, path, "move")), &format!("{}.move", path)));
+    let movement = match text.chars().count() {
+        1 => Movement::from_char(text.chars().next().unwrap()),
+        _ => None
+    };
+    let movement = try!(movement.ok_or_else(|| {
+        invalid(format!("{}.move `{}' is not a valid movement (L, R or N)", path, text))
+    }));
+
+    Ok(Transition {
+        state: next.into(),
+        state_index: None,
+        symbol: write,
+        movement: movement
+    })
+}
+
+impl TMDesc {
+    pub fn from_json(json: &Json) -> Result<TMDesc, TMDescError> {
+        try!(object(json, "the machine"));
+        let mut desc = TMDesc::new();
+
+        let alphabet = try!(array(try!(field(json, "", "alphabet")), ".alphabet"));
+        for (i, s) in alphabet.iter().enumerate() {
+            let c = try!(symbol(s, &format!(".alphabet[{}]", i)));
+            if desc.input_symbols.contains(&c) {
+                return Err(invalid(format!("symbol `{}' is in .alphabet twice", c)));
+            }
+            desc.input_symbols.push(c);
+        }
+        if desc.input_symbols.is_empty() {
+            return Err(invalid(".alphabet is empty".into()));
+        }
+        // The blank is always the last symbol.
+        if let Some(blank) = json.find("blank") {
+            let blank = try!(symbol(blank, ".blank"));
+            match desc.input_symbols.iter().position(|&c| c == blank) {
+                Some(i) => {
+                    desc.input_symbols.remove(i);
+                    desc.input_symbols.push(blank);
+                }
+                None => return Err(invalid(format!("the blank `{}' is not in .alphabet", blank)))
+            }
+        }
+
+        let states = try!(array(try!(field(json, "", "states")), ".states"));
+        for (i, s) in states.iter().enumerate() {
+            let path = format!(".states[{}]", i);
+            try!(object(s, &path));
+            let name = try!(string(try!(field(s, &path, "name")), &format!("{}.name", path)));
+            if desc.find_state(name).is_some() {
+                return Err(invalid(format!("state `{}' is defined twice", name)));
+            }
+
+            let mut state = State::empty(name.into(), desc.input_symbols.len());
+            state.halt = None;
+            if let Some(transitions) = s.find("transitions") {
+                let path = format!("{}.transitions", path);
+                for (key, t) in try!(object(transitions, &path)).iter() {
+                    let path = format!("{}.{}", path, key);
+                    let read = match desc.input_symbols.iter().position(|c| c.to_string() == *key) {
+                        Some(read) => read,
+                        None => return Err(invalid(format!("{} is not for a symbol in .alphabet",
+                                                           path)))
+                    };
+                    state.transitions[read] = Some(try!(transition(t, &path)));
+                }
+            }
+            desc.states.push(state);
+        }
+
+        if let Some(halting) = json.find("halting") {
+            for (key, names) in try!(object(halting, ".halting")).iter() {
+                let path = format!(".halting.{}", key);
+                let kind = match HaltKind::from_directive(&format!("!{}", key)) {
+                    Some(kind) => kind,
+                    None => return Err(invalid(format!("{} is not a kind of final state", path)))
+                };
+                for (i, name) in try!(array(names, &path)).iter().enumerate() {
+                    let name = try!(string(name, &format!("{}[{}]", path, i)));
+                    let index = match desc.find_state(name) {
+                        Some(index) => index,
+                        None => {
+                            let n = desc.input_symbols.len();
+                            desc.states.push(State::empty(name.into(), n));
+                            desc.states.len() - 1
+                        }
+                    };
+                    desc.states[index].halt = Some(kind);
+                }
+            }
+        }
+
+        // The initial state is always the first one.
+        if let Some(initial) = json.find("initial") {
+            let initial = try!(string(initial, ".initial"));
+            match desc.find_state(initial) {
+                Some(i) => {
+                    let state = desc.states.remove(i);
+                    desc.states.insert(0, state);
+                }
+                None => return Err(invalid(format!("the initial state `{}' is not defined",
+                                                   initial)))
+            }
+        }
+        if desc.states.is_empty() {
+            return Err(invalid(".states is empty".into()));
+        }
+
+        for state in desc.states.iter() {
+            for trans in state.transitions.iter().filter_map(|t| t.as_ref()) {
+                if desc.find_state(&trans.state).is_none() {
+                    return Err(invalid(format!("state `{}' is not defined", trans.state)));
+                }
+            }
+        }
+
+        Ok(desc)
+    }
+
+    pub fn from_json_str(s: &str) -> Result<TMDesc, TMDescError> {
+        match Json::from_str(s) {
+            Ok(json) => TMDesc::from_json(&json),
+            Err(ParserError::SyntaxError(code, line, column)) => Err(TMDescError::Malformed {
+                format: Format::Json, line: line, column: column,
+                message: json::error_str(code).into()
+            }),
+            Err(ParserError::IoError(e)) => Err(TMDescError::Io(e))
+        }
+    }
+}
+
+impl ToJson for TMDesc {
+    fn to_json(&self) -> Json {
+        let symbol = |c: char| Json::String(c.to_string());
+
+        let states = self.states.iter().map(|state| {
+            let mut transitions = BTreeMap::new();
+            for (trans, &read) in state.transitions.iter().zip(self.input_symbols.iter()) {
+                if let Some(ref trans) = *trans {
+                    let mut obj = BTreeMap::new();
+                    obj.insert("next".to_string(), Json::String(trans.state.clone()));
+                    obj.insert("write".to_string(), symbol(trans.symbol));
+                    obj.insert("move".to_string(), symbol(trans.movement.to_char()));
+                    transitions.insert(read.to_string(), Json::Object(obj));
+                }
+            }
+            let mut obj = BTreeMap::new();
+            obj.insert("name".to_string(), Json::String(state.name.clone()));
+            obj.insert("transitions".to_string(), Json::Object(transitions));
+            Json::Object(obj)
+        }).collect();
+
+        let mut halting = BTreeMap::new();
+        for &kind in [HaltKind::Halt, HaltKind::Accept, HaltKind::Reject].iter() {
+            let names = self.states.iter().filter(|s| s.halt == Some(kind))
+                .map(|s| Json::String(s.name.clone())).collect::<Vec<_>>();
+            if !names.is_empty() {
+                // The kind without the `!` of the directive
+                halting.insert(kind.directive()[1..].to_string(), Json::Array(names));
+            }
+        }
+
+        let mut obj = BTreeMap::new();
+        obj.insert("alphabet".to_string(),
+                   Json::Array(self.input_symbols.iter().map(|&c| symbol(c)).collect()));
+        if let Some(&blank) = self.input_symbols.last() {
+            obj.insert("blank".to_string(), symbol(blank));
+        }
+        if let Some(initial) = self.states.first() {
+            obj.insert("initial".to_string(), Json::String(initial.name.clone()));
+        }
+        obj.insert("halting".to_string(), Json::Object(halting));
+        obj.insert("states".to_string(), Json::Array(states));
+        Json::Object(obj)
+    }
+}
@@ -3,6 +3,26 @@
 extern crate rustc_serialize;
 
 mod errors;
+mod validate;
+mod trace;
+mod debugger;
+mod history;
+mod dense;
+mod macro_tm;
+mod detect;
+mod configuration;
+mod render;
+mod diagram;
+mod dot;
+mod writer;
+mod format;
+mod json;
+mod busy_beaver;
+mod yaml;
+mod turingmachine_io;
+mod xml;
+mod jflap;
+mod quintuple;
 
 use std::default::Default;
 use std::ops::{Index, IndexMut, Deref};
@@ -10,7 +30,22 @@ use std::path::Path;
 use std::fs::File;
 use std::io::Read;
 
-pub use errors::TMDescError;
+pub use errors::{TMDescError, RunError, RunErrorKind, TableError, ConfigurationError};
+pub use errors::ExportError;
+pub use validate::{Diagnostic, Severity};
+pub use trace::{Tracer, TraceFormat};
+pub use debugger::{Debugger, Breakpoint, Stop};
+pub use dense::{DenseTable, FastTM};
+pub use macro_tm::MacroTM;
+pub use detect::{LoopDetector, RunOutcome, NonHaltingReason};
+pub use configuration::Configuration;
+pub use render::{render, RenderStyle};
+pub use diagram::{SpaceTime, DiagramFormat};
+pub use format::Format;
+
+/// Stands for the current state or the symbol that was read in a transition,
+/// so it can't be the name of a state or a symbol.
+static SAME: &'static str = "=";
 
 #[derive(Debug, Clone, RustcEncodable)]
 pub struct Transition {
@@ -21,22 +56,57 @@ pub struct Transition {
 }
 
 impl Transition {
-    fn from_str(s: &str) -> Transition {
+    /// Parse a transition like `q1,A,R`, which starts at the given line and
+    /// column of the source. It is taken by `state` on the symbol `read`, which
+    /// `=` stands for.
+    fn from_str(s: &str, state: &str, read: char, line: usize, column: usize)
+            -> Result<Transition, TMDescError> {
         let v: Vec<_> = s.split(',').collect();
-        assert_eq!(v.len(), 3);
-        assert_eq!(v[1].chars().count(), 1);
-        assert_eq!(v[2].chars().count(), 1);
+        if v.len() != 3 {
+            return Err(TMDescError::BadTransitionArity {
+                line: line, column: column, text: s.into()
+            });
+        }
+
+        let symbol_column = column + v[0].chars().count() + 1;
+        if v[1].chars().count() != 1 {
+            return Err(TMDescError::MultiCharSymbol {
+                line: line, column: symbol_column, text: v[1].into()
+            });
+        }
+
+        let movement_column = symbol_column + v[1].chars().count() + 1;
+        let movement = if v[2].chars().count() == 1 {
+            Movement::from_char(v[2].chars().next().unwrap())
+        } else {
+            None
+        };
+        let movement = match movement {
+            Some(movement) => movement,
+            None => return Err(TMDescError::InvalidMovement {
+                line: line, column: movement_column, text: v[2].into()
+            })
+        };
 
-        Transition {
-            state: v[0].into(),
+        Ok(Transition {
+            state: if v[0] == SAME { state.into() } else { v[0].into() },
             state_index: None,
-            symbol: v[1].chars().next().unwrap(),
-            movement: Movement::from_char(v[2].chars().next().unwrap())
+            symbol: if v[1] == SAME { read } else { v[1].chars().next().unwrap() },
+            movement: movement
+        })
+    }
+
+    /// Parse an entry of a row, which is a transition or `-`.
+    fn from_entry(s: &str, state: &str, read: char, line: usize, column: usize)
+            -> Result<Option<Transition>, TMDescError> {
+        match s {
+            "-" => Ok(None),
+            s => Transition::from_str(s, state, read, line, column).map(Some)
         }
     }
 }
 
-#[derive(Debug, Clone, RustcEncodable)]
+#[derive(Debug, Clone, Copy, PartialEq, RustcEncodable)]
 pub enum Movement {
     Left,
     Right,
@@ -44,12 +114,20 @@ pub enum Movement {
 }
 
 impl Movement {
-    fn from_char(c: char) -> Movement {
+    fn from_char(c: char) -> Option<Movement> {
         match c {
-            'L' => Movement::Left,
-            'R' => Movement::Right,
-            'N' => Movement::None,
-            bad => panic!("`{}' is not a valid movement!", bad)
+            'L' => Some(Movement::Left),
+            'R' => Some(Movement::Right),
+            'N' => Some(Movement::None),
+            _ => None
+        }
+    }
+
+    pub fn to_char(&self) -> char {
+        match *self {
+            Movement::Left => 'L',
+            Movement::None => 'N',
+            Movement::Right => 'R'
         }
     }
 
@@ -63,29 +141,122 @@ impl Movement {
 }
 
 
+/// The different kinds of final states. The discriminants are shared with
+/// libturingrt.
+#[derive(Debug, Clone, Copy, PartialEq, RustcEncodable)]
+pub enum HaltKind {
+    /// Just stop, without a verdict.
+    Halt = 0,
+    Accept = 1,
+    Reject = 2
+}
+
+impl HaltKind {
+    /// The directive that declares states of this kind in a TM file.
+    pub fn directive(&self) -> &'static str {
+        match *self {
+            HaltKind::Halt => "!halt",
+            HaltKind::Accept => "!accept",
+            HaltKind::Reject => "!reject"
+        }
+    }
+
+    fn from_directive(s: &str) -> Option<HaltKind> {
+        match s {
+            "!halt" => Some(HaltKind::Halt),
+            "!accept" => Some(HaltKind::Accept),
+            "!reject" => Some(HaltKind::Reject),
+            _ => None
+        }
+    }
+}
+
 #[derive(Debug, Clone, RustcEncodable)]
 pub struct State {
     pub name: String,
-    pub transitions: Vec<Option<Transition>>
+    pub transitions: Vec<Option<Transition>>,
+    /// `Some` for final states.
+    pub halt: Option<HaltKind>
 }
 
 impl State {
-    fn new(name: String, words: &[&str]) -> State {
-        let trans = words.iter().map(|&w| {
-            match w {
-                "-" => None,
-                w => Some(Transition::from_str(w))
+    /// Build a state from the entries of its row, one for each of `symbols`.
+    /// `columns` holds the source column of each entry.
+    fn new(name: String, symbols: &[char], words: &[&str], line: usize, columns: &[usize])
+            -> Result<State, TMDescError> {
+        let mut trans = Vec::with_capacity(words.len());
+        for ((&w, &column), &read) in words.iter().zip(columns.iter()).zip(symbols.iter()) {
+            trans.push(try!(Transition::from_entry(w, &name, read, line, column)));
+        }
+
+        let mut state = State::empty(name, 0);
+        state.transitions = trans;
+        Ok(state)
+    }
+
+    /// Build a state from a row of keyed entries like `A:q1,B,R`, which can
+    /// be in any order. The entry `*:` applies to all symbols that don't have
+    /// an entry of their own (including a symbol called `*`), and symbols
+    /// without any entry have no transition.
+    fn from_keyed(name: String, symbols: &[char], words: &[&str], line: usize,
+                  columns: &[usize]) -> Result<State, TMDescError> {
+        let mut trans = vec![None; symbols.len()];
+        let mut given = vec![false; symbols.len()];
+        let mut default = None;
+        for (&w, &column) in words.iter().zip(columns.iter()) {
+            let key = w.chars().next().unwrap();
+            // The transition starts after the key and the colon.
+            let (entry, entry_column) = (&w[key.len_utf8() + 1..], column + 2);
+            if key == '*' {
+                if default.is_some() {
+                    return Err(TMDescError::DuplicateEntry {
+                        line: line, column: column, text: key.to_string()
+                    });
+                }
+                default = Some((entry, entry_column));
+                continue;
+            }
+
+            let index = match symbols.iter().position(|&c| c == key) {
+                Some(index) => index,
+                None => return Err(TMDescError::UnknownSymbol {
+                    line: line, column: column, text: key.to_string()
+                })
+            };
+            if given[index] {
+                return Err(TMDescError::DuplicateEntry {
+                    line: line, column: column, text: key.to_string()
+                });
             }
-        }).collect();
+            given[index] = true;
+            trans[index] = try!(Transition::from_entry(entry, &name, key, line, entry_column));
+        }
+
+        if let Some((entry, column)) = default {
+            for (i, &read) in symbols.iter().enumerate().filter(|&(i, _)| !given[i]) {
+                trans[i] = try!(Transition::from_entry(entry, &name, read, line, column));
+            }
+        }
+
+        let mut state = State::empty(name, 0);
+        state.transitions = trans;
+        Ok(state)
+    }
 
+    /// A state without any transitions. For compatibility with old TM files,
+    /// a state called `STOPP` is final unless halting states are declared
+    /// explicitly.
+    pub fn empty(name: String, num_symbols: usize) -> State {
+        let halt = if name == "STOPP" { Some(HaltKind::Halt) } else { None };
         State {
             name: name,
-            transitions: trans
+            transitions: vec![None; num_symbols],
+            halt: halt
         }
     }
 
     pub fn is_final(&self) -> bool {
-        self.name == "STOPP"
+        self.halt.is_some()
     }
 }
 
@@ -94,6 +265,28 @@ impl State {
 //
 // input symbols        A                       B   C
 // transition table     state,symbol,movement ...
+//
+// A state row without any entries (like a lone `STOPP`) has no transitions.
+// Entries may be padded with spaces on the right, to line up the columns.
+//
+// In a transition, `=` as the state stays in the same state, and `=` as the
+// symbol writes back the symbol that was read. So `=,=,L` just moves left.
+// That's why no state or symbol may be called `=`.
+//
+// keyed rows           q0   A:q1,B,R   *:=,=,R
+// Instead of one entry per symbol, a row may consist of entries that start
+// with a symbol and a colon, in any order. `*:` gives the transition for all
+// symbols without an entry of their own, and symbols without any entry have
+// no transition. A row is keyed if all of its entries look like this. As `*:`
+// is always the default, a symbol called `*` can't have an entry of its own in
+// a keyed row; it only gets the default.
+//
+// halting states       !accept   ACCEPT ...
+//                      !reject   REJECT ...
+//                      !halt     STOPP ...
+// Halting states may be declared anywhere, and don't need a row of their own,
+// but a state can only be declared with one kind of directive.
+// Without any such declarations, a state called STOPP is the halting state.
 #[derive(Default, Debug, Clone, RustcEncodable)]
 pub struct TMDesc {
     pub input_symbols: Vec<char>,
@@ -114,28 +307,184 @@ impl TMDesc {
         Some(line.split('\t').collect())
     }
 
-    pub fn handle_line(&mut self, words: &[&str]) {
-        if self.input_symbols.is_empty() {
-            for word in words.iter().skip(1) {
-                assert_eq!(word.chars().count(), 1);
+    /// The source column of each word of a line that was split by
+    /// `parse_line`.
+    fn word_columns(words: &[&str]) -> Vec<usize> {
+        let mut column = 1;
+        words.iter().map(|w| {
+            let this = column;
+            column += w.chars().count() + 1;
+            this
+        }).collect()
+    }
+
+    /// Remove the padding after a word. A lone space is a word of its own,
+    /// though.
+    fn trim_word(word: &str) -> &str {
+        match word.trim_right_matches(' ') {
+            "" if !word.is_empty() => &word[..1],
+            trimmed => trimmed
+        }
+    }
+
+    /// Whether all entries of a row start with a key, like `A:q1,B,R`.
+    fn is_keyed(entries: &[&str]) -> bool {
+        entries.iter().all(|entry| {
+            let mut chars = entry.chars();
+            chars.next().is_some() && chars.next() == Some(':')
+        })
+    }
+
+    fn is_directive(words: &[&str]) -> bool {
+        words[0].starts_with("!")
+    }
+
+    /// Fail if a symbol or state would be called `=`.
+    fn check_name(line: usize, column: usize, name: &str) -> Result<(), TMDescError> {
+        if name == SAME {
+            return Err(TMDescError::ReservedName {
+                line: line, column: column, text: name.into()
+            });
+        }
+        Ok(())
+    }
+
+    /// Handle one line of a TM description file, which has been split into
+    /// words by `parse_line`. `line` is only used for error messages.
+    ///
+    /// Halting state declarations are applied immediately, so the states
+    /// have to be defined already.
+    pub fn handle_line(&mut self, line: usize, words: &[&str])
+            -> Result<(), TMDescError> {
+        let columns = Self::word_columns(words);
+        let words = words.iter().map(|w| Self::trim_word(w)).collect::<Vec<_>>();
+        let words = &words[..];
+
+        if Self::is_directive(words) {
+            let kind = match HaltKind::from_directive(words[0]) {
+                Some(kind) => kind,
+                None => return Err(TMDescError::UnknownDirective {
+                    line: line, column: 1, text: words[0].into()
+                })
+            };
+            for (&name, &column) in words.iter().zip(columns.iter()).skip(1) {
+                try!(Self::check_name(line, column, name));
+                match self.states.iter_mut().find(|s| s.name == name) {
+                    Some(&mut State { halt: Some(previous), .. }) if previous != kind => {
+                        return Err(TMDescError::ConflictingDirective {
+                            line: line, column: column, text: name.into(),
+                            previous: previous.directive()
+                        });
+                    }
+                    Some(state) => state.halt = Some(kind),
+                    None => return Err(TMDescError::UnknownState {
+                        line: line, column: column, text: name.into()
+                    })
+                }
+            }
+        } else if self.input_symbols.is_empty() {
+            for (word, &column) in words.iter().zip(columns.iter()).skip(1) {
+                if word.chars().count() != 1 {
+                    return Err(TMDescError::MultiCharSymbol {
+                        line: line, column: column, text: (*word).into()
+                    });
+                }
+                try!(Self::check_name(line, column, word));
                 self.input_symbols.push(word.chars().next().unwrap())
             }
         } else { // a new state
             let name = words[0];
-            let state = State::new(name.into(), &words[1..]);
+            try!(Self::check_name(line, 1, name));
+            if self.states.iter().any(|s| s.name == name) {
+                return Err(TMDescError::DuplicateState {
+                    line: line, column: 1, text: name.into()
+                });
+            }
+
+            let entries = &words[1..];
+            let symbols = &self.input_symbols;
+            let state = if entries.is_empty() {
+                State::empty(name.into(), symbols.len())
+            } else if Self::is_keyed(entries) {
+                try!(State::from_keyed(name.into(), symbols, entries, line, &columns[1..]))
+            } else if entries.len() == symbols.len() {
+                try!(State::new(name.into(), symbols, entries, line, &columns[1..]))
+            } else {
+                return Err(TMDescError::RowLength {
+                    line: line, column: 1, text: name.into(),
+                    expected: self.input_symbols.len(), found: entries.len()
+                });
+            };
             self.states.push(state);
         }
+
+        Ok(())
+    }
+
+    /// Look up a state by name.
+    pub fn find_state(&self, name: &str) -> Option<usize> {
+        self.states.iter().position(|s| s.name == name)
+    }
+
+    /// Give the states that aren't final a transition for every symbol, for
+    /// formats whose machines stop when there is no transition. The new
+    /// transitions keep the symbol and go to the first final state of the
+    /// given kind. If there is none, one called `name` is added, with primes
+    /// appended if another state already has that name.
+    fn stop_when_stuck(&mut self, kind: HaltKind, name: &str) {
+        let stuck = self.states.iter().any(|s| {
+            !s.is_final() && s.transitions.iter().any(|t| t.is_none())
+        });
+        if !stuck {
+            return;
+        }
+
+        let mut name = String::from(name);
+        if let Some(state) = self.states.iter().find(|s| s.halt == Some(kind)) {
+            name = state.name.clone();
+        }
+        loop {
+            match self.find_state(&name) {
+                Some(i) if self.states[i].halt == Some(kind) => break,
+                Some(_) => name.push('\''),
+                None => {
+                    let mut state = State::empty(name.clone(), self.input_symbols.len());
+                    state.halt = Some(kind);
+                    self.states.push(state);
+                    break;
+                }
+            }
+        }
+
+        let symbols = &self.input_symbols;
+        for state in self.states.iter_mut().filter(|s| !s.is_final()) {
+            for (trans, &read) in state.transitions.iter_mut().zip(symbols.iter()) {
+                if trans.is_none() {
+                    *trans = Some(Transition {
+                        state: name.clone(),
+                        state_index: None,
+                        symbol: read,
+                        movement: Movement::None
+                    });
+                }
+            }
+        }
+    }
+
+    /// Whether a transition of the machine only stops it in a final state of
+    /// the given kind, without changing the tape. These are the transitions
+    /// that `stop_when_stuck` adds.
+    fn only_stops(&self, trans: &Transition, read: char, kind: HaltKind) -> bool {
+        trans.movement == Movement::None && trans.symbol == read &&
+            self.find_state(&trans.state).map_or(false, |i| self.states[i].halt == Some(kind))
     }
 
     pub fn resolve_state_index(&self, trans: &Transition) -> usize {
         if let Some(index) = trans.state_index {
             index
         } else {
-            self.states.iter().enumerate()
-                .find(|&(_, s)| s.name == trans.state)
-                .map(|(i, _)| i)
-                .unwrap_or_else(||
-                        panic!("state \"{}\" not found", trans.state))
+            self.find_state(&trans.state).unwrap_or_else(||
+                    panic!("state \"{}\" not found", trans.state))
         }
     }
 
@@ -158,6 +507,18 @@ impl TMDesc {
         }
     }
 
+    /// Check that a machine can start at all: it needs an initial state, and a
+    /// blank symbol.
+    fn check_runnable(&self) -> Result<(), Diagnostic> {
+        if self.input_symbols.is_empty() {
+            Err(Diagnostic::NoSymbols)
+        } else if self.states.is_empty() {
+            Err(Diagnostic::NoStates)
+        } else {
+            Ok(())
+        }
+    }
+
     pub fn blank_symbol(&self) -> char {
         *self.input_symbols.last().unwrap()
     }
@@ -167,35 +528,127 @@ impl TMDesc {
         let mut string = String::new();
 
         try!(file.read_to_string(&mut string));
-        Ok(Self::from_string(&string))
+        Self::from_string(&string)
     }
 
-    pub fn from_string(string: &str) -> TMDesc {
-        let lines = string.lines().filter_map(Self::parse_line).collect::<Vec<_>>();
-        Self::from_lines(lines)
+    /// Read a TM file, or rules in the quintuple format (see quintuple.rs).
+    pub fn from_string(string: &str) -> Result<TMDesc, TMDescError> {
+        if quintuple::is_quintuples(string) {
+            return Self::from_quintuples(string);
+        }
+        let lines = string.lines().enumerate().filter_map(|(i, line)|
+            Self::parse_line(line).map(|words| (i + 1, words))
+        ).collect::<Vec<_>>();
+        Self::from_numbered_lines(lines)
     }
 
-    pub fn from_lines<'a, I, S>(lines: I) -> TMDesc where I: IntoIterator<Item=S>, S: Deref<Target=[&'a str]> {
+    /// Build a TM description from lines that have already been split into
+    /// words. Lines are numbered from 1 in error messages.
+    pub fn from_lines<'a, I, S>(lines: I) -> Result<TMDesc, TMDescError>
+            where I: IntoIterator<Item=S>, S: Deref<Target=[&'a str]> {
+        Self::from_numbered_lines(lines.into_iter().enumerate().map(
+            |(i, line)| (i + 1, line)
+        ))
+    }
+
+    fn from_numbered_lines<'a, I, S>(lines: I) -> Result<TMDesc, TMDescError>
+            where I: IntoIterator<Item=(usize, S)>, S: Deref<Target=[&'a str]> {
+        let lines = lines.into_iter().collect::<Vec<_>>();
+
         let mut desc = Self::new();
-        for line in lines {
-            desc.handle_line(&*line)
+        for &(line, ref words) in lines.iter() {
+            if !Self::is_directive(&*words) {
+                try!(desc.handle_line(line, &*words));
+            }
+        }
+
+        // Halting states are declared after all the rows are known, so that
+        // they can be declared anywhere. Declared states without a row of
+        // their own are added at the end.
+        let directives = lines.iter().filter(|l| Self::is_directive(&*l.1))
+            .collect::<Vec<_>>();
+        if !directives.is_empty() {
+            for state in desc.states.iter_mut() {
+                state.halt = None;
+            }
+        }
+        for &&(line, ref words) in directives.iter() {
+            for name in words.iter().skip(1).map(|w| Self::trim_word(w)) {
+                if desc.find_state(name).is_none() {
+                    // Not even a state called STOPP halts by default here.
+                    let mut state = State::empty(name.into(), desc.input_symbols.len());
+                    state.halt = None;
+                    desc.states.push(state);
+                }
+            }
+            try!(desc.handle_line(line, &*words));
         }
-        desc
+
+        // Now that all states are known, check that every transition leads
+        // somewhere. The first line is the header, so it's skipped.
+        let rows = lines.iter().filter(|l| !Self::is_directive(&*l.1));
+        for &(line, ref words) in rows.skip(1) {
+            let columns = Self::word_columns(&*words);
+            let entries = words.iter().skip(1).map(|w| Self::trim_word(w)).collect::<Vec<_>>();
+            let keyed = Self::is_keyed(&entries);
+            for (word, &column) in entries.iter().zip(columns.iter().skip(1)) {
+                // Skip the key of keyed entries.
+                let (word, column) = if keyed {
+                    (&word[word.chars().next().unwrap().len_utf8() + 1..], column + 2)
+                } else {
+                    (*word, column)
+                };
+                if word == "-" {
+                    continue;
+                }
+                let target = word.split(',').next().unwrap();
+                if target != SAME && !desc.states.iter().any(|s| s.name == target) {
+                    return Err(TMDescError::UnknownState {
+                        line: line, column: column, text: target.into()
+                    });
+                }
+            }
+        }
+
+        Ok(desc)
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Tape {
     left: Vec<char>,
-    right: Vec<char>
+    right: Vec<char>,
+    blank: char
 }
 
 impl Tape {
-    pub fn from_str(s: &str) -> Tape {
+    /// An empty tape, which is padded with `blank` as needed.
+    pub fn new(blank: char) -> Tape {
+        Tape::from_str("", blank)
+    }
+
+    /// A tape that contains `s`, starting at index 0.
+    pub fn from_str(s: &str, blank: char) -> Tape {
         Tape {
             left: vec![],
-            right: s.chars().collect()
+            right: s.chars().collect(),
+            blank: blank
+        }
+    }
+
+    /// A tape that contains `s`, starting at index `start`.
+    pub fn from_str_at(s: &str, start: isize, blank: char) -> Tape {
+        let mut tape = Tape::new(blank);
+        for (i, c) in s.chars().enumerate() {
+            let index = start + i as isize;
+            tape.ensure_space(index);
+            tape[index] = c;
         }
+        tape
+    }
+
+    pub fn blank(&self) -> char {
+        self.blank
     }
 
     /// maximum index plus one. the name is slightly misleading.
@@ -204,18 +657,38 @@ impl Tape {
     }
 
     /// minimum index.
-    fn min(&self) -> isize {
+    pub fn min(&self) -> isize {
         -(self.left.len() as isize)
     }
 
     /// add blanks to ensure that the given index is valid.
     pub fn ensure_space(&mut self, i: isize) {
         if i >= self.max() {
-            self.right.resize(i as usize + 1, 'B')
+            self.right.resize(i as usize + 1, self.blank)
         } else if i < self.min() {
-            self.left.resize(-i as usize, 'B')
+            self.left.resize(-i as usize, self.blank)
         }
     }
+
+    /// The symbol at index `i`, or a blank if that part of the tape hasn't
+    /// been used yet.
+    pub fn get(&self, i: isize) -> char {
+        if i >= self.min() && i < self.max() {
+            self[i]
+        } else {
+            self.blank
+        }
+    }
+
+    /// The symbols from index `from` up to (but not including) `to`.
+    pub fn window(&self, from: isize, to: isize) -> String {
+        (from..to).map(|i| self.get(i)).collect()
+    }
+
+    /// Like `to_string`, but without leading and trailing blanks.
+    pub fn to_trimmed_string(&self) -> String {
+        self.to_string().trim_matches(self.blank).into()
+    }
 }
 
 impl ToString for Tape {
@@ -253,55 +726,183 @@ impl Index<isize> for Tape {
     }
 }
 
+/// What happened during a call to `TM::run_step`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StepOutcome {
+    /// A step was executed, and the machine can go on.
+    Continue,
+    /// The machine is in a final state of the given kind.
+    Halted(HaltKind),
+    /// There's no transition for the current state and symbol (a `-` in the
+    /// table), so the machine can't move on.
+    Stuck
+}
+
+/// Everything that happened in one step of a `TM`.
+#[derive(Debug, Clone, Copy)]
+pub struct Step<'a> {
+    /// The number of the step, starting at 1.
+    pub number: u64,
+    pub state: &'a State,
+    /// The head position before the step
+    pub head: isize,
+    pub read: char,
+    pub written: char,
+    pub movement: Movement,
+    pub next_state: &'a State
+}
+
 /// a runnable turing machine instance
 pub struct TM<'a> {
     desc: &'a TMDesc,
     head: isize,
     tape: Tape,
-    state: &'a State
+    state: &'a State,
+    state_index: usize,
+    steps: u64,
+    last_step: Option<Step<'a>>,
+    history: Option<history::History>
 }
 
 impl<'a> TM<'a> {
-    pub fn new(desc: &'a TMDesc, input: &str) -> TM<'a> {
-        TM {
+    /// Start a machine with `input` on the tape. A description without
+    /// symbols or states, like `TMDesc::new()`, can't be run.
+    pub fn new(desc: &'a TMDesc, input: &str) -> Result<TM<'a>, Diagnostic> {
+        try!(desc.check_runnable());
+        Ok(TM {
             desc: desc,
             head: 0,
             tape: {
-                let mut tape = Tape::from_str(input);
+                let mut tape = Tape::from_str(input, desc.blank_symbol());
                 tape.ensure_space(0);
                 tape
             },
-            state: &desc.states[0]
-        }
+            state: &desc.states[0],
+            state_index: 0,
+            steps: 0,
+            last_step: None,
+            history: None
+        })
     }
 
-    pub fn run_step(&mut self) {
-        if self.has_finished() {
-            return;
+    /// Execute one step. Once the machine has halted or got stuck, this
+    /// doesn't change anything and keeps returning the same outcome.
+    pub fn run_step(&mut self) -> Result<StepOutcome, RunError> {
+        self.last_step = None;
+        if let Some(kind) = self.state.halt {
+            return Ok(StepOutcome::Halted(kind));
         }
 
         let cur_sym = self.tape[self.head];
         let input_index = match self.desc.input_symbols.iter()
-        .enumerate().find(|&(_i, &sym)| sym == cur_sym) {
-            Some((index, _)) => index,
-            None => panic!("Input character \'{}\' was not found", cur_sym)
+        .position(|&sym| sym == cur_sym) {
+            Some(index) => index,
+            None => return Err(self.error(RunErrorKind::UnknownSymbol))
         };
 
-        let trans = match self.state.transitions[input_index] {
-            Some(ref trans) => trans,
-            None => panic!("No transition for {} on \'{}\'", self.state.name, input_index)
+        let trans = match self.state.transitions.get(input_index) {
+            Some(&Some(ref trans)) => trans,
+            _ => return Ok(StepOutcome::Stuck)
         };
 
-        let state_index = self.desc.resolve_state_index(trans);
+        let state_index = match trans.state_index {
+            Some(index) => index,
+            None => match self.desc.find_state(&trans.state) {
+                Some(index) => index,
+                None => return Err(self.error(
+                        RunErrorKind::UnknownState(trans.state.clone())))
+            }
+        };
+
+        if let Some(ref mut history) = self.history {
+            history.record(history::Undo {
+                state: self.state_index,
+                head: self.head,
+                symbol: cur_sym
+            });
+        }
 
-        self.state = &self.desc.states[state_index];
+        let next_state = &self.desc.states[state_index];
+        self.steps += 1;
+        self.last_step = Some(Step {
+            number: self.steps,
+            state: self.state,
+            head: self.head,
+            read: cur_sym,
+            written: trans.symbol,
+            movement: trans.movement,
+            next_state: next_state
+        });
+
+        self.state = next_state;
+        self.state_index = state_index;
         self.tape[self.head] = trans.symbol;
         self.head += trans.movement.to_delta();
         self.tape.ensure_space(self.head);
+
+        if self.history.as_ref().map_or(false, |h| h.wants_checkpoint(self.steps)) {
+            let checkpoint = history::Checkpoint::of(self);
+            self.history.as_mut().unwrap().add_checkpoint(checkpoint);
+        }
+
+        match self.state.halt {
+            Some(kind) => Ok(StepOutcome::Halted(kind)),
+            None => Ok(StepOutcome::Continue)
+        }
+    }
+
+    fn error(&self, kind: RunErrorKind) -> RunError {
+        RunError {
+            kind: kind,
+            state: self.state.name.clone(),
+            head: self.head,
+            symbol: self.tape[self.head],
+            step: self.steps
+        }
+    }
+
+    pub fn desc(&self) -> &'a TMDesc {
+        self.desc
+    }
+
+    pub fn head(&self) -> isize {
+        self.head
+    }
+
+    pub fn tape(&self) -> &Tape {
+        &self.tape
+    }
+
+    /// The current state. After the machine has halted, this tells which of
+    /// the final states was reached.
+    pub fn state(&self) -> &'a State {
+        self.state
+    }
+
+    /// The index of the current state in `desc().states`.
+    pub fn state_index(&self) -> usize {
+        self.state_index
+    }
+
+    /// The number of steps executed so far.
+    pub fn steps(&self) -> u64 {
+        self.steps
+    }
+
+    /// The transition that the next step will follow, if there is one.
+    pub fn current_transition(&self) -> Option<&'a Transition> {
+        if self.state.is_final() {
+            return None;
+        }
+        let cur_sym = self.tape[self.head];
+        self.desc.input_symbols.iter().position(|&sym| sym == cur_sym)
+            .and_then(|index| self.state.transitions.get(index))
+            .and_then(|trans| trans.as_ref())
     }
 
-    pub fn has_finished(&self) -> bool {
-        self.state.is_final()
+    /// The step that was executed by the last call to `run_step`, if any.
+    pub fn last_step(&self) -> Option<Step<'a>> {
+        self.last_step
     }
 
     pub fn get_tape_output(&self) -> String {
@@ -0,0 +1,472 @@
+//! Accelerated simulation with macro steps, for machines that run for
+//! billions of steps and more.
+//!
+//! `MacroTM` cuts the tape into blocks of `k` cells and keeps it run-length
+//! encoded: each side of the head is a stack of (block, count) pairs. The
+//! head always sits on the border between two blocks, facing one of them. A
+//! macro step runs the machine on the block it faces until it leaves the
+//! block again; the result only depends on the state, the side and the block,
+//! so it is computed once and memoized.
+//!
+//! When the machine leaves a block on the far side in the same state it
+//! entered, it will do the same for every copy of that block in the run, so
+//! the whole run is swept in one go. Sweeps across the infinite blank part of
+//! the tape, and loops within a single block, are recognized as running
+//! forever.
+
+use std::cmp;
+use std::collections::{HashMap, HashSet};
+use std::u64;
+use {TMDesc, State, Tape, StepOutcome, RunError, RunErrorKind, TableError};
+use {Configuration, ConfigurationError};
+use dense::DenseTable;
+
+/// The side of the head that a block is on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Side {
+    Left,
+    Right
+}
+
+impl Side {
+    fn other(self) -> Side {
+        match self {
+            Side::Left => Side::Right,
+            Side::Right => Side::Left
+        }
+    }
+}
+
+/// What happens when the machine enters a block. `reach` is the leftmost and
+/// rightmost cell of the block that the head was on.
+#[derive(Debug, Clone, Copy)]
+enum BlockResult {
+    /// The machine leaves the block on `side`, having turned it into
+    /// `block`.
+    Exit { block: usize, state: usize, side: Side, steps: u64, reach: (usize, usize) },
+    /// The machine halts or gets stuck inside the block, at cell `pos`.
+    Stop { block: usize, state: usize, pos: usize, steps: u64, reach: (usize, usize) },
+    /// The machine never leaves the block.
+    Loop
+}
+
+/// A stack of blocks, the top is next to the head.
+type Stack = Vec<(usize, u64)>;
+
+/// A machine that executes macro steps instead of single steps.
+pub struct MacroTM<'a> {
+    table: DenseTable<'a>,
+    block_size: usize,
+    /// The cells of every block seen so far. Block 0 is all blanks.
+    blocks: Vec<Vec<u8>>,
+    block_ids: HashMap<Vec<u8>, usize>,
+    results: HashMap<(usize, Side, usize), BlockResult>,
+    left: Stack,
+    right: Stack,
+    /// The position of the leftmost cell of `right`
+    border: i64,
+    /// The part of the tape that has been used, like the tape of a `TM`
+    low: i64,
+    high: i64,
+    /// The side the head faces
+    facing: Side,
+    /// When the machine stopped inside a block, the cell of the top block on
+    /// the `facing` side that the head is on.
+    inside: Option<usize>,
+    state: usize,
+    steps: u64,
+    never_halts: bool,
+    /// Unknown symbols can only come from the input, so they are looked up
+    /// here.
+    input: Vec<char>,
+    /// The position of the first symbol of `input`
+    input_start: i64
+}
+
+impl<'a> MacroTM<'a> {
+    /// Create a machine with blocks of `block_size` cells. Larger blocks
+    /// help with machines that move back and forth in small patterns, but
+    /// there are more of them to compute.
+    pub fn new(desc: &'a TMDesc, input: &str, block_size: usize)
+            -> Result<MacroTM<'a>, TableError> {
+        let table = try!(DenseTable::new(desc));
+        Ok(MacroTM::with_tape(table, block_size, input.chars().collect(), 0, 0, 0, 0))
+    }
+
+    /// Continue a run of a machine from a saved configuration, with blocks
+    /// of `block_size` cells.
+    pub fn from_configuration(desc: &'a TMDesc, config: &Configuration, block_size: usize)
+            -> Result<MacroTM<'a>, ConfigurationError> {
+        let state = try!(config.check(desc));
+        let table = try!(DenseTable::new(desc).map_err(ConfigurationError::Table));
+        Ok(MacroTM::with_tape(table, block_size, config.tape.chars().collect(),
+                              config.tape_start as i64, config.head as i64, state,
+                              config.steps))
+    }
+
+    /// A machine whose tape holds `input` from position `start` on. The
+    /// blocks start at the head, which faces right.
+    fn with_tape(table: DenseTable<'a>, block_size: usize, input: Vec<char>, start: i64,
+                 head: i64, state: usize, steps: u64) -> MacroTM<'a> {
+        assert!(block_size > 0);
+        let blank = table.blank();
+        let end = start + input.len() as i64;
+        let symbols = input.iter().map(|&c| table.symbol_index(c)).collect::<Vec<_>>();
+
+        let mut tm = MacroTM {
+            table: table,
+            block_size: block_size,
+            blocks: vec![],
+            block_ids: HashMap::new(),
+            results: HashMap::new(),
+            left: vec![],
+            right: vec![],
+            border: head,
+            low: cmp::min(cmp::min(start, head), 0),
+            high: cmp::max(cmp::max(end, head + 1), 0),
+            facing: Side::Right,
+            inside: None,
+            state: state,
+            steps: steps,
+            never_halts: false,
+            input: input,
+            input_start: start
+        };
+        tm.intern(vec![blank; block_size]);
+
+        // Beyond the input, the stacks are empty, which stands for blanks.
+        let cell = |position: i64| if position >= start && position < end {
+            symbols[(position - start) as usize]
+        } else {
+            blank
+        };
+        let size = block_size as i64;
+        let right = (end - head + size - 1) / size;
+        for i in (0..right).rev() {
+            let cells = (0..size).map(|j| cell(head + i * size + j)).collect();
+            let block = tm.intern(cells);
+            push(&mut tm.right, block, 1);
+        }
+        let left = (head - start + size - 1) / size;
+        for i in (0..left).rev() {
+            let cells = (0..size).map(|j| cell(head - (i + 1) * size + j)).collect();
+            let block = tm.intern(cells);
+            push(&mut tm.left, block, 1);
+        }
+        tm
+    }
+
+    /// The symbol that the tape started with at a position, for symbols
+    /// that aren't input symbols.
+    fn input_symbol(&self, position: i64) -> char {
+        self.input[(position - self.input_start) as usize]
+    }
+
+    /// Run until the machine halts or gets stuck, or until at least
+    /// `max_steps` steps have been executed in total. In the latter case,
+    /// `Continue` is returned. A macro step can't be split, so the machine
+    /// may go a bit past the limit.
+    ///
+    /// `Continue` is also returned right away once the machine is known to
+    /// run forever, see `never_halts`.
+    pub fn run(&mut self, max_steps: Option<u64>) -> Result<StepOutcome, RunError> {
+        let limit = max_steps.unwrap_or(u64::MAX);
+        while self.inside.is_none() && !self.never_halts && self.steps < limit {
+            if self.table.halt(self.state).is_some() {
+                break;
+            }
+            self.macro_step(limit);
+        }
+        self.outcome()
+    }
+
+    fn macro_step(&mut self, limit: u64) {
+        let facing = self.facing;
+        // An empty stack is an endless run of blanks.
+        let (block, count) = match self.stack(facing).last() {
+            Some(&(block, count)) => (block, Some(count)),
+            None => (0, None)
+        };
+        let state = self.state;
+        let result = self.block_result(state, facing, block);
+        let start = match facing {
+            Side::Left => self.border - self.block_size as i64,
+            Side::Right => self.border
+        };
+
+        match result {
+            BlockResult::Exit { block: new_block, state: new_state, side, steps, reach } => {
+                // Sweep across the whole run if the machine ends up doing the
+                // same thing on the next block.
+                let count = if side == facing && new_state == state {
+                    let count = match count {
+                        Some(count) => count,
+                        None => {
+                            self.never_halts = true;
+                            return;
+                        }
+                    };
+                    let remaining = (limit - self.steps - 1) / steps + 1;
+                    if count < remaining { count } else { remaining }
+                } else {
+                    1
+                };
+                pop(self.stack_mut(facing), count);
+                push(self.stack_mut(side.other()), new_block, count);
+                if side == facing {
+                    self.move_border(side, count);
+                }
+                self.state = new_state;
+                self.facing = side;
+                self.steps = self.steps.saturating_add(steps.saturating_mul(count));
+
+                // A sweep visits all the blocks it passes.
+                let (low, high) = match (side == facing, side) {
+                    (true, Side::Left) => (self.border, start + reach.1 as i64),
+                    (true, Side::Right) => (start + reach.0 as i64, self.border - 1),
+                    (false, _) => (start + reach.0 as i64, start + reach.1 as i64)
+                };
+                let head = self.head() as i64;
+                self.visit(low, high);
+                self.visit(head, head);
+            }
+            BlockResult::Stop { block: new_block, state: new_state, pos, steps, reach } => {
+                pop(self.stack_mut(facing), 1);
+                push(self.stack_mut(facing), new_block, 1);
+                self.state = new_state;
+                self.inside = Some(pos);
+                self.steps = self.steps.saturating_add(steps);
+                self.visit(start + reach.0 as i64, start + reach.1 as i64);
+            }
+            BlockResult::Loop => self.never_halts = true
+        }
+    }
+
+    /// Mark the cells from `low` to `high` (inclusive) as used.
+    fn visit(&mut self, low: i64, high: i64) {
+        self.low = cmp::min(self.low, low);
+        self.high = cmp::max(self.high, high + 1);
+    }
+
+    /// Move the border past `count` blocks, towards `side`.
+    fn move_border(&mut self, side: Side, count: u64) {
+        let cells = (self.block_size as i64).saturating_mul(count as i64);
+        self.border = match side {
+            Side::Left => self.border.saturating_sub(cells),
+            Side::Right => self.border.saturating_add(cells)
+        };
+    }
+
+    fn block_result(&mut self, state: usize, side: Side, block: usize) -> BlockResult {
+        if let Some(&result) = self.results.get(&(state, side, block)) {
+            return result;
+        }
+        let result = self.simulate(state, side, block);
+        self.results.insert((state, side, block), result);
+        result
+    }
+
+    /// Run the machine on a single block, which is on `side` of the head.
+    fn simulate(&mut self, mut state: usize, side: Side, block: usize) -> BlockResult {
+        let mut cells = self.blocks[block].clone();
+        let mut pos = match side {
+            Side::Left => self.block_size - 1,
+            Side::Right => 0
+        };
+        let mut steps = 0;
+        let mut reach = (pos, pos);
+        let mut seen = HashSet::new();
+
+        loop {
+            let transition = match self.table.halt(state) {
+                Some(_) => None,
+                None => self.table.lookup(state, cells[pos])
+            };
+            let (next, symbol, movement) = match transition {
+                Some(transition) => transition,
+                None => return BlockResult::Stop {
+                    block: self.intern(cells),
+                    state: state,
+                    pos: pos,
+                    steps: steps,
+                    reach: reach
+                }
+            };
+            if !seen.insert((state, pos, cells.clone())) {
+                return BlockResult::Loop;
+            }
+
+            cells[pos] = symbol;
+            state = next;
+            steps += 1;
+
+            let pos_after = pos as isize + movement.to_delta();
+            if pos_after < 0 || pos_after as usize == self.block_size {
+                return BlockResult::Exit {
+                    block: self.intern(cells),
+                    state: state,
+                    side: if pos_after < 0 { Side::Left } else { Side::Right },
+                    steps: steps,
+                    reach: reach
+                };
+            }
+            pos = pos_after as usize;
+            reach = (cmp::min(reach.0, pos), cmp::max(reach.1, pos));
+        }
+    }
+
+    fn intern(&mut self, cells: Vec<u8>) -> usize {
+        if let Some(&id) = self.block_ids.get(&cells) {
+            return id;
+        }
+        let id = self.blocks.len();
+        self.blocks.push(cells.clone());
+        self.block_ids.insert(cells, id);
+        id
+    }
+
+    fn stack(&self, side: Side) -> &Stack {
+        match side {
+            Side::Left => &self.left,
+            Side::Right => &self.right
+        }
+    }
+
+    fn stack_mut(&mut self, side: Side) -> &mut Stack {
+        match side {
+            Side::Left => &mut self.left,
+            Side::Right => &mut self.right
+        }
+    }
+
+    /// The block the head faces, and where the head is in it.
+    fn current_cell(&self) -> (usize, usize) {
+        let block = self.stack(self.facing).last().map_or(0, |&(block, _)| block);
+        let pos = match (self.inside, self.facing) {
+            (Some(pos), _) => pos,
+            (None, Side::Left) => self.block_size - 1,
+            (None, Side::Right) => 0
+        };
+        (block, pos)
+    }
+
+    /// What `run` returns without executing any steps.
+    fn outcome(&self) -> Result<StepOutcome, RunError> {
+        if let Some(kind) = self.table.halt(self.state) {
+            return Ok(StepOutcome::Halted(kind));
+        }
+        let (block, pos) = self.current_cell();
+        let symbol = self.blocks[block][pos];
+        if self.never_halts || self.table.lookup(self.state, symbol).is_some() {
+            Ok(StepOutcome::Continue)
+        } else if symbol == self.table.unknown() {
+            Err(RunError {
+                kind: RunErrorKind::UnknownSymbol,
+                state: self.state().name.clone(),
+                head: self.head(),
+                symbol: self.input_symbol(self.head() as i64),
+                step: self.steps
+            })
+        } else {
+            Ok(StepOutcome::Stuck)
+        }
+    }
+
+    /// `true` if the machine was found to run forever, either because it
+    /// sweeps across the blank part of the tape, or because it is caught in a
+    /// loop within a block. The machine then stays at the step where the
+    /// endless part begins.
+    pub fn never_halts(&self) -> bool {
+        self.never_halts
+    }
+
+    pub fn block_size(&self) -> usize {
+        self.block_size
+    }
+
+    pub fn steps(&self) -> u64 {
+        self.steps
+    }
+
+    pub fn head(&self) -> isize {
+        let (_, pos) = self.current_cell();
+        let start = match self.facing {
+            Side::Left => self.border - self.block_size as i64,
+            Side::Right => self.border
+        };
+        (start + pos as i64) as isize
+    }
+
+    /// The symbol under the head.
+    pub fn symbol(&self) -> char {
+        let (block, pos) = self.current_cell();
+        match self.table.symbol(self.blocks[block][pos]) {
+            Some(c) => c,
+            None => self.input_symbol(self.head() as i64)
+        }
+    }
+
+    pub fn state(&self) -> &'a State {
+        &self.table.desc().states[self.state]
+    }
+
+    pub fn state_index(&self) -> usize {
+        self.state
+    }
+
+    /// A copy of the tape. Beware that after a long run, the tape can be
+    /// much too long for this.
+    pub fn tape(&self) -> Tape {
+        let desc = self.table.desc();
+        let left_blocks = self.left.iter().fold(0, |sum, &(_, count)| sum + count);
+        let start = self.border - left_blocks as i64 * self.block_size as i64;
+
+        let blocks = self.left.iter().chain(self.right.iter().rev());
+        let mut s = String::new();
+        let mut position = start;
+        for &(block, count) in blocks {
+            for _ in 0..count {
+                for &symbol in self.blocks[block].iter() {
+                    if position >= self.low && position < self.high {
+                        s.push(match self.table.symbol(symbol) {
+                            Some(c) => c,
+                            None => self.input_symbol(position)
+                        });
+                    }
+                    position += 1;
+                }
+            }
+        }
+        // The used part may reach into the blanks beyond the stacks.
+        let first = cmp::max(start, self.low);
+        let mut tape = Tape::from_str_at(&s, first as isize, desc.blank_symbol());
+        tape.ensure_space(self.low as isize);
+        tape.ensure_space(self.high as isize - 1);
+        tape
+    }
+}
+
+fn push(stack: &mut Stack, block: usize, count: u64) {
+    if let Some(&mut (top, ref mut top_count)) = stack.last_mut() {
+        if top == block {
+            *top_count = top_count.saturating_add(count);
+            return;
+        }
+    }
+    stack.push((block, count));
+}
+
+/// Remove `count` copies of the top block. An empty stack stands for blanks
+/// all the way, so nothing needs to be removed from it.
+fn pop(stack: &mut Stack, count: u64) {
+    let empty = match stack.last_mut() {
+        Some(&mut (_, ref mut top_count)) => {
+            *top_count -= count;
+            *top_count == 0
+        }
+        None => false
+    };
+    if empty {
+        stack.pop();
+    }
+}
@@ -0,0 +1,291 @@
+//! The line-based rule format of Anthony Morphett's simulator, with one
+//! quintuple per line:
+//!
+//! ```text
+//! ; <current state> <current symbol> <new symbol> <direction> <new state>
+//! 0 * * r 0
+//! 0 _ 1 l halt
+//! ```
+//!
+//! `_` is the blank, and the direction is `l`, `r` or `*` (no movement). A
+//! `*` matches any state or symbol, or keeps it unchanged in the new symbol
+//! and state. Rules for a specific state and symbol take precedence over
+//! rules with a `*` symbol, which take precedence over rules for any state.
+//! States whose name starts with `halt` are final: `halt-accept` accepts,
+//! `halt-reject` rejects, and the others just halt. The initial state is `0`,
+//! or the state of the first rule if there is no `0`. Comments start with
+//! `;`, and a `!` at the end of a rule (a breakpoint) is ignored.
+//!
+//! In a `TMDesc`, the rules are expanded to a transition for every state and
+//! symbol, so `*` only matches the symbols that the rules mention.
+//!
+//! `TMDesc::from_string` reads quintuples as well, if there are no tabs, which
+//! separate the cells of a table, and the first line that isn't empty or a
+//! comment is a rule.
+
+use {TMDesc, TMDescError, ExportError, State, Transition, Movement, HaltKind, Format};
+
+/// A rule, with `None` for `*`
+struct Rule<'a> {
+    line: usize,
+    column: usize,
+    state: Option<&'a str>,
+    read: Option<char>,
+    write: Option<char>,
+    movement: Movement,
+    next: Option<&'a str>
+}
+
+/// The blank symbol
+const BLANK: char = '_';
+
+fn error(line: usize, column: usize, message: String) -> TMDescError {
+    TMDescError::Malformed {
+        format: Format::Quintuple, line: line, column: column, message: message
+    }
+}
+
+/// The halting kind of a state name
+fn halt_kind(name: &str) -> Option<HaltKind> {
+    match name {
+        "halt-accept" => Some(HaltKind::Accept),
+        "halt-reject" => Some(HaltKind::Reject),
+        _ if name.starts_with("halt") => Some(HaltKind::Halt),
+        _ => None
+    }
+}
+
+fn wildcard<T>(word: &str, value: T) -> Option<T> {
+    if word == "*" { None } else { Some(value) }
+}
+
+/// Split a line into words, with their columns.
+fn words(line: &str) -> Vec<(usize, &str)> {
+    let mut words = vec![];
+    let mut start = None;
+    let mut column = 1;
+    for (i, c) in line.char_indices() {
+        match (c.is_whitespace(), start) {
+            (true, Some((s, col))) => {
+                words.push((col, &line[s..i]));
+                start = None;
+            }
+            (false, None) => start = Some((i, column)),
+            _ => ()
+        }
+        column += 1;
+    }
+    if let Some((s, col)) = start {
+        words.push((col, &line[s..]));
+    }
+    words
+}
+
+fn parse_rule<'a>(line: usize, text: &'a str) -> Result<Option<Rule<'a>>, TMDescError> {
+    let text = text.split(';').next().unwrap();
+    let mut words = words(text);
+    if words.last().map_or(false, |&(_, w)| w == "!") {
+        words.pop();
+    }
+    if words.is_empty() {
+        return Ok(None);
+    }
+    if words.len() != 5 {
+        return Err(error(line, words[0].0,
+                         format!("a rule needs 5 parts, not {}", words.len())));
+    }
+
+    let symbol = |(column, word): (usize, &str)| -> Result<Option<char>, TMDescError> {
+        let mut chars = word.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Ok(wildcard(word, c)),
+            _ => Err(error(line, column,
+                           format!("symbol `{}' is not a single character", word)))
+        }
+    };
+    let movement = match words[3].1 {
+        "l" | "L" => Movement::Left,
+        "r" | "R" => Movement::Right,
+        "*" => Movement::None,
+        word => return Err(error(line, words[3].0,
+                                 format!("`{}' is not a valid direction (l, r or *)", word)))
+    };
+
+    Ok(Some(Rule {
+        line: line,
+        column: words[0].0,
+        state: wildcard(words[0].1, words[0].1),
+        read: try!(symbol(words[1])),
+        write: try!(symbol(words[2])),
+        movement: movement,
+        next: wildcard(words[4].1, words[4].1)
+    }))
+}
+
+/// Whether a text holds quintuples rather than a TM file. A TM file always
+/// has tabs between its cells, and the header can look just like a rule (as
+/// in `x\ta\tb\tl\tc`), so only texts without tabs are considered. Then the
+/// first line that isn't empty decides; a `#` comment belongs to a TM file.
+pub fn is_quintuples(s: &str) -> bool {
+    if s.contains('\t') {
+        return false;
+    }
+    for (i, line) in s.lines().enumerate() {
+        if line.is_empty() {
+            continue;
+        }
+        if line.starts_with("#") {
+            return false;
+        }
+        match parse_rule(i + 1, line) {
+            Ok(None) => (),
+            Ok(Some(_)) => return true,
+            Err(_) => return false
+        }
+    }
+    false
+}
+
+impl TMDesc {
+    pub fn from_quintuples(s: &str) -> Result<TMDesc, TMDescError> {
+        let mut rules: Vec<Rule> = vec![];
+        for (i, line) in s.lines().enumerate() {
+            if let Some(rule) = try!(parse_rule(i + 1, line)) {
+                if let Some(other) = rules.iter().find(|r| r.state == rule.state &&
+                                                       r.read == rule.read) {
+                    return Err(error(rule.line, rule.column,
+                                     format!("this rule is already defined on line {}",
+                                             other.line)));
+                }
+                rules.push(rule);
+            }
+        }
+        if rules.is_empty() {
+            return Err(error(0, 0, "there are no rules".into()));
+        }
+
+        let mut desc = TMDesc::new();
+        // The states and symbols, in order of appearance
+        let mut names: Vec<&str> = vec![];
+        for rule in rules.iter() {
+            for name in [rule.state, rule.next].iter().filter_map(|&n| n) {
+                if !names.contains(&name) {
+                    names.push(name);
+                }
+            }
+            for &c in [rule.read, rule.write].iter().filter_map(|c| c.as_ref()) {
+                if c != BLANK && !desc.input_symbols.contains(&c) {
+                    desc.input_symbols.push(c);
+                }
+            }
+        }
+        desc.input_symbols.push(BLANK);
+        if names.is_empty() {
+            return Err(error(0, 0, "there are no states".into()));
+        }
+        // The initial state goes first.
+        let initial = names.iter().position(|&n| n == "0").unwrap_or(0);
+        let name = names.remove(initial);
+        names.insert(0, name);
+
+        for &name in names.iter() {
+            let mut state = State::empty(name.into(), desc.input_symbols.len());
+            state.halt = halt_kind(name);
+            if !state.is_final() {
+                for (i, &read) in desc.input_symbols.iter().enumerate() {
+                    // The most specific rule wins.
+                    let patterns = [(Some(name), Some(read)), (Some(name), None),
+                                    (None, Some(read)), (None, None)];
+                    let rule = patterns.iter().filter_map(|&(s, c)| {
+                        rules.iter().find(|r| r.state == s && r.read == c)
+                    }).next();
+                    state.transitions[i] = rule.map(|rule| Transition {
+                        state: rule.next.unwrap_or(name).into(),
+                        state_index: None,
+                        symbol: rule.write.unwrap_or(read),
+                        movement: rule.movement
+                    });
+                }
+            }
+            desc.states.push(state);
+        }
+        Ok(desc)
+    }
+
+    /// Write the machine as quintuples, starting with the rules of the
+    /// initial state. Final states are written as `halt`, `halt-accept` and
+    /// `halt-reject`. The blank is written as `_`.
+    ///
+    /// This fails if a symbol or a state can't be written, if a state that
+    /// isn't final starts with `halt`, or if the initial state wouldn't be
+    /// recognized.
+    pub fn to_quintuples(&self) -> Result<String, ExportError> {
+        let error = |reason: String| ExportError { format: Format::Quintuple, reason: reason };
+        if self.states.is_empty() || self.input_symbols.is_empty() {
+            return Err(error("there are no states or no symbols".into()));
+        }
+        let blank = self.blank_symbol();
+
+        let symbol = |c: char| {
+            if c == blank {
+                Ok(BLANK)
+            } else if c == BLANK || c == '*' || c == ';' || c.is_whitespace() {
+                Err(error(format!("symbol `{}' can't be written", c)))
+            } else {
+                Ok(c)
+            }
+        };
+        let names = try!(self.states.iter().map(|state| {
+            let name = match state.halt {
+                Some(HaltKind::Halt) => "halt",
+                Some(HaltKind::Accept) => "halt-accept",
+                Some(HaltKind::Reject) => "halt-reject",
+                None if halt_kind(&state.name).is_some() =>
+                    return Err(error(format!("state `{}' would be final", state.name))),
+                None => &state.name[..]
+            };
+            let bad = |c: char| c.is_whitespace() || c == ';';
+            if name.is_empty() || name == "*" || name == "!" || name.chars().any(bad) {
+                return Err(error(format!("state `{}' can't be written", name)));
+            }
+            Ok(name)
+        }).collect::<Result<Vec<_>, _>>());
+
+        // The initial state is `0`, or the state of the first rule.
+        let has_rules = |state: &State| state.transitions.iter().any(|t| t.is_some());
+        if names[0] != "0" {
+            if names.iter().skip(1).any(|&n| n == "0") {
+                return Err(error("state `0' would be the initial state".into()));
+            }
+            if self.states[0].is_final() || !has_rules(&self.states[0]) {
+                return Err(error("the initial state has no rules".into()));
+            }
+        }
+
+        let mut rules = String::new();
+        for (state, &name) in self.states.iter().zip(names.iter()) {
+            if state.is_final() {
+                continue;
+            }
+            let pairs = state.transitions.iter().zip(self.input_symbols.iter());
+            for (trans, &read) in pairs {
+                let trans = match *trans {
+                    Some(ref trans) => trans,
+                    None => continue
+                };
+                let next = match self.find_state(&trans.state) {
+                    Some(next) => names[next],
+                    None => return Err(error(format!("state `{}' is not defined", trans.state)))
+                };
+                let direction = match trans.movement {
+                    Movement::Left => 'l',
+                    Movement::Right => 'r',
+                    Movement::None => '*'
+                };
+                rules.push_str(&format!("{} {} {} {} {}\n", name, try!(symbol(read)),
+                                        try!(symbol(trans.symbol)), direction, next));
+            }
+        }
+        Ok(rules)
+    }
+}
@@ -0,0 +1,60 @@
+//! Rendering configurations in the classic `…B1[q3]01B…` notation: the tape
+//! around the head, with the current state in brackets right before the cell
+//! that the head is on.
+
+use std::{cmp, fmt};
+use {TM, Tape};
+
+/// How many cells to each side of the head `TM`'s `Display` shows.
+const DISPLAY_WINDOW: usize = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RenderStyle {
+    Plain,
+    /// With ANSI escape sequences that highlight the state and the head, for
+    /// terminals.
+    Ansi
+}
+
+/// Render a configuration. Only `window` cells to each side of the head are
+/// shown, or the whole used part of the tape if `window` is `None`. An
+/// ellipsis marks used parts of the tape that are cut off.
+pub fn render(tape: &Tape, head: isize, state: &str, window: Option<usize>,
+              style: RenderStyle) -> String {
+    let (from, to) = match window {
+        Some(window) => (head - window as isize, head + window as isize + 1),
+        None => (cmp::min(tape.min(), head), cmp::max(tape.max(), head + 1))
+    };
+
+    let mut s = String::new();
+    if from > tape.min() {
+        s.push('…');
+    }
+    s.push_str(&tape.window(from, head));
+    match style {
+        RenderStyle::Plain => {
+            s.push_str(&format!("[{}]{}", state, tape.get(head)));
+        }
+        RenderStyle::Ansi => {
+            s.push_str(&format!("\x1b[1;36m[{}]\x1b[0m\x1b[7m{}\x1b[0m", state, tape.get(head)));
+        }
+    }
+    s.push_str(&tape.window(head + 1, to));
+    if to < tape.max() {
+        s.push('…');
+    }
+    s
+}
+
+impl<'a> TM<'a> {
+    /// Render the current configuration, see `render`.
+    pub fn render(&self, window: Option<usize>, style: RenderStyle) -> String {
+        render(&self.tape, self.head, &self.state.name, window, style)
+    }
+}
+
+impl<'a> fmt::Display for TM<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.render(Some(DISPLAY_WINDOW), RenderStyle::Plain))
+    }
+}
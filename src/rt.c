@@ -17,14 +17,32 @@ typedef uint32_t *(*tm_func_t)(uint32_t *);
 
 #define PREFIX "libturingrt: "		/* a prefix for error messages */
 
-extern void tm_run(tm_func_t , uint32_t *, uint32_t);
+/* The kinds of final states, as in turing_machines::HaltKind */
+enum halt_kind {
+	TM_HALT = 0,
+	TM_ACCEPT = 1,
+	TM_REJECT = 2,
+};
+
+/* Set by the turing machine when it reaches a final state */
+const char *tm_halt_state;
+uint32_t tm_halt_kind;
+int tm_exit_status;
+
+/* The exit status for tm_fail, as passed to tm_run */
+static int tm_fail_status = EXIT_FAILURE;
+
+extern int tm_run(tm_func_t , uint32_t *, uint32_t, int);
 extern void tm_fail(const char *state, uint32_t symbol);
 
 static void read_input(char *, size_t, wchar_t *, uint32_t *, uint32_t);
 static void write_output(wchar_t *, uint32_t *, uint32_t);
 
-extern void tm_run(tm_func_t fn, uint32_t *isyms, uint32_t num_isyms)
+extern int tm_run(tm_func_t fn, uint32_t *isyms, uint32_t num_isyms,
+		int fail_status)
 {
+	tm_fail_status = fail_status;
+
 	// setup the memory
 	uint8_t *mmap_res = mmap(NULL, TAPE_SIZE, PROT_READ|PROT_WRITE,
 			MAP_PRIVATE|MAP_ANONYMOUS, -1, 0);
@@ -53,6 +71,13 @@ extern void tm_run(tm_func_t fn, uint32_t *isyms, uint32_t num_isyms)
 	uint32_t *new_tp = fn((uint32_t *)tape_start);
 
 	write_output(new_tp, isyms, num_isyms);
+
+	if (tm_halt_kind == TM_ACCEPT)
+		fprintf(stderr, PREFIX "accepted in state %s\n", tm_halt_state);
+	else if (tm_halt_kind == TM_REJECT)
+		fprintf(stderr, PREFIX "rejected in state %s\n", tm_halt_state);
+
+	return tm_exit_status;
 }
 
 static bool is_isym(uint32_t which, uint32_t *isyms, uint32_t num_isyms)
@@ -130,6 +155,6 @@ extern void tm_fail(const char *state, uint32_t symbol)
 		P("No transition from %s on symbol U+%04X\n", state, symbol);
 	}
 
-	exit(EXIT_FAILURE);
+	exit(tm_fail_status);
 }
 #undef P
@@ -0,0 +1,92 @@
+//! Step-by-step traces of running turing machines.
+
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+use rustc_serialize::json::Json;
+use {TM, RenderStyle};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TraceFormat {
+    /// One line of text per step
+    Human,
+    /// One JSON object per line and step
+    Json
+}
+
+impl TraceFormat {
+    pub fn parse(s: &str) -> Option<TraceFormat> {
+        match s {
+            "human" => Some(TraceFormat::Human),
+            "json" => Some(TraceFormat::Json),
+            _ => None
+        }
+    }
+}
+
+/// Writes a trace of a turing machine, one step at a time.
+///
+/// Each step is written together with the tape after it, `window` cells to the
+/// left and right of the new head position. Human-readable traces show it as a
+/// configuration, JSON traces as a string of symbols.
+pub struct Tracer<W: Write> {
+    out: W,
+    format: TraceFormat,
+    window: usize,
+    style: RenderStyle
+}
+
+impl<W: Write> Tracer<W> {
+    pub fn new(out: W, format: TraceFormat, window: usize) -> Tracer<W> {
+        Tracer {
+            out: out,
+            format: format,
+            window: window,
+            style: RenderStyle::Plain
+        }
+    }
+
+    /// Set how configurations are shown in human-readable traces.
+    pub fn set_style(&mut self, style: RenderStyle) {
+        self.style = style;
+    }
+
+    /// Write the step that the machine has just executed. Nothing is written
+    /// if `run_step` didn't execute a step.
+    pub fn trace(&mut self, tm: &TM) -> io::Result<()> {
+        let step = match tm.last_step() {
+            Some(step) => step,
+            None => return Ok(())
+        };
+
+        match self.format {
+            TraceFormat::Human => {
+                writeln!(self.out, "{:>8}  {} @ {}: '{}' -> '{}' {} {}  {}",
+                         step.number, step.state.name, step.head, step.read,
+                         step.written, step.movement.to_char(),
+                         step.next_state.name, tm.render(Some(self.window), self.style))
+            }
+            TraceFormat::Json => {
+                let from = tm.head() - self.window as isize;
+                let to = tm.head() + self.window as isize + 1;
+                let window = tm.tape().window(from, to);
+
+                let mut obj = BTreeMap::new();
+                obj.insert("step".to_string(), Json::U64(step.number));
+                obj.insert("state".to_string(), Json::String(step.state.name.clone()));
+                obj.insert("head".to_string(), Json::I64(step.head as i64));
+                obj.insert("read".to_string(), Json::String(step.read.to_string()));
+                obj.insert("write".to_string(), Json::String(step.written.to_string()));
+                obj.insert("move".to_string(),
+                           Json::String(step.movement.to_char().to_string()));
+                obj.insert("next".to_string(), Json::String(step.next_state.name.clone()));
+                obj.insert("window".to_string(), Json::String(window));
+                obj.insert("window_start".to_string(), Json::I64(from as i64));
+                writeln!(self.out, "{}", Json::Object(obj))
+            }
+        }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+}
@@ -0,0 +1,289 @@
+//! The YAML documents of the turingmachine.io simulator, which look like
+//! this:
+//!
+//! ```text
+//! blank: ' '
+//! start state: right
+//! table:
+//!   right:
+//!     [1,0]: R
+//!     ' '  : {L: carry}
+//!   carry:
+//!     1      : {write: 0, L}
+//!     [0,' ']: {write: 1, L: done}
+//!   done:
+//! ```
+//!
+//! Each state maps symbols (or lists of symbols) to an instruction. `R` and
+//! `L` just move the head, and `{write: S, L: next}` also writes a symbol
+//! and changes the state. The symbol and the state stay the same if they are
+//! left out. Machines halt when there is no instruction, so states without
+//! any are final states in a `TMDesc`. The other states move to a final
+//! state on the symbols they have no instruction for (a new one called
+//! `halt`, if there is none).
+//!
+//! The symbols are the blank, the symbols of `input` (if given), and all
+//! symbols of the table, in order of appearance. `name` and other fields are
+//! ignored.
+
+use yaml::{self, Yaml, Entry};
+use {TMDesc, TMDescError, ExportError, State, Transition, Movement, HaltKind, Format};
+
+fn error(line: usize, column: usize, message: String) -> TMDescError {
+    TMDescError::Malformed { format: Format::Yaml, line: line, column: column, message: message }
+}
+
+/// A single character from a scalar.
+fn symbol(value: &Yaml, entry: &Entry) -> Result<char, TMDescError> {
+    let s = match value.as_str() {
+        Some(s) => s,
+        None => return Err(error(entry.line, entry.column, "expected a symbol".into()))
+    };
+    if s.chars().count() != 1 {
+        return Err(error(entry.line, entry.column,
+                         format!("symbol `{}' is not a single character", s)));
+    }
+    Ok(s.chars().next().unwrap())
+}
+
+fn add_symbol(desc: &mut TMDesc, c: char) {
+    if !desc.input_symbols.contains(&c) {
+        desc.input_symbols.push(c);
+    }
+}
+
+/// The symbols of the key of an instruction.
+fn read_symbols(entry: &Entry) -> Result<Vec<char>, TMDescError> {
+    match entry.key {
+        Yaml::Seq(ref items) => items.iter().map(|item| symbol(item, entry)).collect(),
+        ref key => Ok(vec![try!(symbol(key, entry))])
+    }
+}
+
+/// An instruction of `state` for the symbol `read`.
+fn instruction(entry: &Entry, state: &str, read: char) -> Result<Transition, TMDescError> {
+    let mut trans = Transition {
+        state: state.into(),
+        state_index: None,
+        symbol: read,
+        movement: Movement::None
+    };
+    let fields = match entry.value {
+        Yaml::Scalar(ref s) => vec![(&s[..], None, entry)],
+        Yaml::Map(ref fields) => {
+            let mut v = vec![];
+            for field in fields.iter() {
+                match field.key.as_str() {
+                    Some(key) => v.push((key, Some(&field.value), field)),
+                    None => return Err(error(field.line, field.column, "expected a key".into()))
+                }
+            }
+            v
+        }
+        _ => return Err(error(entry.line, entry.column, "expected an instruction".into()))
+    };
+
+    let mut moved = false;
+    for &(key, value, field) in fields.iter() {
+        match key {
+            "write" => trans.symbol = try!(symbol(value.unwrap_or(&Yaml::Null), field)),
+            "L" | "R" if !moved => {
+                moved = true;
+                trans.movement = if key == "L" { Movement::Left } else { Movement::Right };
+                match value {
+                    Some(&Yaml::Scalar(ref next)) => trans.state = next.clone(),
+                    Some(&Yaml::Null) | None => (),
+                    Some(_) => return Err(error(field.line, field.column,
+                                                "expected a state".into()))
+                }
+            }
+            "L" | "R" => return Err(error(field.line, field.column,
+                                          "the head can only move once".into())),
+            key => return Err(error(field.line, field.column,
+                                    format!("unknown instruction `{}'", key)))
+        }
+    }
+    if !moved {
+        return Err(error(entry.line, entry.column, "the instruction needs L or R".into()));
+    }
+    Ok(trans)
+}
+
+impl TMDesc {
+    pub fn from_yaml(s: &str) -> Result<TMDesc, TMDescError> {
+        let doc = try!(yaml::parse(s).map_err(|e| error(e.line, e.column, e.message)));
+        let field = |name: &str| doc.get(name).ok_or_else(|| {
+            error(0, 0, format!("`{}' is missing", name))
+        });
+
+        let blank = try!(field("blank"));
+        let blank = try!(symbol(&blank.value, blank));
+        let start = try!(field("start state"));
+        let start_name = match start.value.as_str() {
+            Some(name) => name,
+            None => return Err(error(start.line, start.column, "expected a state".into()))
+        };
+        let table = try!(field("table"));
+        let states = match table.value {
+            Yaml::Map(ref states) => states,
+            _ => return Err(error(table.line, table.column, "expected states".into()))
+        };
+
+        let mut desc = TMDesc::new();
+        if let Some(input) = doc.get("input").and_then(|e| e.value.as_str()) {
+            for c in input.chars() {
+                add_symbol(&mut desc, c);
+            }
+        }
+
+        // The instructions are collected first, because the symbols are only
+        // known at the end. The targets are checked once all states exist.
+        let mut table = vec![];
+        let mut targets = vec![];
+        for entry in states.iter() {
+            let name = match entry.key.as_str() {
+                Some(name) => name,
+                None => return Err(error(entry.line, entry.column, "expected a state".into()))
+            };
+            if table.iter().any(|&(n, _)| n == name) {
+                return Err(error(entry.line, entry.column,
+                                 format!("state `{}' is defined twice", name)));
+            }
+            let mut transitions: Vec<(char, Transition)> = vec![];
+            match entry.value {
+                Yaml::Null => (),
+                Yaml::Map(ref instructions) => for instr in instructions.iter() {
+                    for read in try!(read_symbols(instr)) {
+                        if transitions.iter().any(|&(c, _)| c == read) {
+                            return Err(error(instr.line, instr.column,
+                                             format!("symbol `{}' has two instructions", read)));
+                        }
+                        let trans = try!(instruction(instr, name, read));
+                        targets.push((trans.state.clone(), instr.line, instr.column));
+                        add_symbol(&mut desc, read);
+                        add_symbol(&mut desc, trans.symbol);
+                        transitions.push((read, trans));
+                    }
+                },
+                _ => return Err(error(entry.line, entry.column, "expected instructions".into()))
+            }
+            table.push((name, transitions));
+        }
+        // The blank goes last.
+        desc.input_symbols.retain(|&c| c != blank);
+        desc.input_symbols.push(blank);
+
+        for &(name, ref transitions) in table.iter() {
+            let mut state = State::empty(name.into(), desc.input_symbols.len());
+            state.halt = if transitions.is_empty() { Some(HaltKind::Halt) } else { None };
+            for &(read, ref trans) in transitions.iter() {
+                let index = desc.input_symbols.iter().position(|&c| c == read).unwrap();
+                state.transitions[index] = Some(trans.clone());
+            }
+            desc.states.push(state);
+        }
+        for &(ref target, line, column) in targets.iter() {
+            if desc.find_state(target).is_none() {
+                return Err(error(line, column, format!("state `{}' is not defined", target)));
+            }
+        }
+        desc.stop_when_stuck(HaltKind::Halt, "halt");
+
+        // The start state is always the first one.
+        match desc.find_state(start_name) {
+            Some(i) => {
+                let state = desc.states.remove(i);
+                desc.states.insert(0, state);
+            }
+            None => return Err(error(start.line, start.column,
+                                     format!("state `{}' is not defined", start_name)))
+        }
+        Ok(desc)
+    }
+
+    /// Write the machine as a turingmachine.io document. Final states are
+    /// written without instructions, and symbols with the same instruction
+    /// are grouped. Transitions that just halt are left out, because the
+    /// simulator halts without an instruction anyway.
+    ///
+    /// This fails for machines with `N` movements, which the simulator
+    /// lacks.
+    pub fn to_yaml(&self) -> Result<String, ExportError> {
+        let mut yaml = String::new();
+        if self.states.is_empty() || self.input_symbols.is_empty() {
+            return Err(ExportError {
+                format: Format::Yaml, reason: "there are no states or no symbols".into()
+            });
+        }
+        yaml.push_str(&format!("blank: {}\n", quote(&self.blank_symbol().to_string())));
+        yaml.push_str(&format!("start state: {}\n", quote(&self.states[0].name)));
+        yaml.push_str("table:\n");
+
+        for state in self.states.iter() {
+            yaml.push_str(&format!("  {}:\n", quote(&state.name)));
+            if state.is_final() {
+                continue;
+            }
+
+            // The instructions, and the symbols that they are for
+            let mut instructions: Vec<(String, Vec<String>)> = vec![];
+            let pairs = state.transitions.iter().zip(self.input_symbols.iter());
+            for (trans, &read) in pairs {
+                let trans = match *trans {
+                    Some(ref trans) if !self.only_stops(trans, read, HaltKind::Halt) => trans,
+                    _ => continue
+                };
+                let movement = match trans.movement {
+                    Movement::Left => "L",
+                    Movement::Right => "R",
+                    Movement::None => return Err(ExportError {
+                        format: Format::Yaml,
+                        reason: format!("state {} doesn't move on `{}'", state.name, read)
+                    })
+                };
+                let instr = if trans.symbol == read && trans.state == state.name {
+                    movement.into()
+                } else {
+                    let mut fields = vec![];
+                    if trans.symbol != read {
+                        fields.push(format!("write: {}", quote(&trans.symbol.to_string())));
+                    }
+                    if trans.state != state.name {
+                        fields.push(format!("{}: {}", movement, quote(&trans.state)));
+                    } else {
+                        fields.push(movement.into());
+                    }
+                    format!("{{{}}}", fields.join(", "))
+                };
+
+                let read = quote(&read.to_string());
+                match instructions.iter().position(|&(ref i, _)| *i == instr) {
+                    Some(index) => instructions[index].1.push(read),
+                    None => instructions.push((instr, vec![read]))
+                }
+            }
+
+            for (instr, reads) in instructions {
+                let key = if reads.len() == 1 {
+                    reads[0].clone()
+                } else {
+                    format!("[{}]", reads.join(", "))
+                };
+                yaml.push_str(&format!("    {}: {}\n", key, instr));
+            }
+        }
+        Ok(yaml)
+    }
+}
+
+/// Quote a symbol or state name in single quotes, unless it is safe as a
+/// plain scalar.
+fn quote(s: &str) -> String {
+    let plain = s.chars().all(|c| c.is_alphanumeric() || c == '_') &&
+        s != "null" && !s.is_empty();
+    if plain {
+        s.into()
+    } else {
+        format!("'{}'", s.replace("'", "''"))
+    }
+}
@@ -0,0 +1,186 @@
+//! Semantic checks on a complete `TMDesc`.
+
+use self::Diagnostic::*;
+use std::fmt::{self, Display, Formatter};
+use TMDesc;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Severity {
+    /// The machine can't be run (or will crash) like this.
+    Error,
+    /// The machine works, but something looks like a mistake.
+    Warning
+}
+
+/// A problem found by `TMDesc::validate`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Diagnostic {
+    /// The header doesn't list any symbols, so there is no blank symbol.
+    NoSymbols,
+    NoStates,
+    DuplicateSymbol(char),
+    DuplicateState(String),
+    RowLength { state: String, expected: usize, found: usize },
+    /// The transition of `state` on `symbol` leads to a state that doesn't
+    /// exist.
+    UnknownState { state: String, symbol: char, target: String },
+    /// The transition of `state` on `symbol` writes a symbol that's not in
+    /// the header.
+    UnknownSymbol { state: String, symbol: char, written: char },
+    NoFinalState,
+    /// No transition leads to this state, and it isn't the initial state.
+    Unreachable(String),
+    /// Final states are never left, so their transitions are ignored.
+    FinalTransitions(String),
+}
+
+impl Diagnostic {
+    pub fn severity(&self) -> Severity {
+        match *self {
+            Unreachable(_) | FinalTransitions(_) => Severity::Warning,
+            _ => Severity::Error
+        }
+    }
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let severity = match self.severity() {
+            Severity::Error => "error",
+            Severity::Warning => "warning"
+        };
+        try!(write!(f, "{}: ", severity));
+
+        match self {
+            &NoSymbols => write!(f, "no symbols (and thus no blank symbol)"),
+            &NoStates => write!(f, "no states"),
+            &DuplicateSymbol(sym) =>
+                write!(f, "symbol `{}' is listed more than once", sym),
+            &DuplicateState(ref name) =>
+                write!(f, "state `{}' is defined more than once", name),
+            &RowLength { ref state, expected, found } =>
+                write!(f, "state `{}' has {} entries, but there are {} symbols",
+                       state, found, expected),
+            &UnknownState { ref state, symbol, ref target } =>
+                write!(f, "transition from `{}' on `{}' leads to unknown \
+                       state `{}'", state, symbol, target),
+            &UnknownSymbol { ref state, symbol, written } =>
+                write!(f, "transition from `{}' on `{}' writes unknown \
+                       symbol `{}'", state, symbol, written),
+            &NoFinalState => write!(f, "there is no final state"),
+            &Unreachable(ref name) =>
+                write!(f, "state `{}' is unreachable", name),
+            &FinalTransitions(ref name) =>
+                write!(f, "final state `{}' has transitions, which are \
+                       ignored", name),
+        }
+    }
+}
+
+impl TMDesc {
+    /// Check the whole description and return every problem that was found,
+    /// errors and warnings alike. An empty result means the machine is fine.
+    pub fn validate(&self) -> Vec<Diagnostic> {
+        let mut diags = vec![];
+
+        if self.input_symbols.is_empty() {
+            diags.push(NoSymbols);
+        }
+        if self.states.is_empty() {
+            diags.push(NoStates);
+        }
+
+        for (i, &sym) in self.input_symbols.iter().enumerate() {
+            // Only report the second occurrence, so that each duplicate is
+            // reported once.
+            if self.input_symbols[..i].iter().filter(|&&s| s == sym).count() == 1 {
+                diags.push(DuplicateSymbol(sym));
+            }
+        }
+
+        for (i, state) in self.states.iter().enumerate() {
+            if self.states[..i].iter().filter(|s| s.name == state.name).count() == 1 {
+                diags.push(DuplicateState(state.name.clone()));
+            }
+        }
+
+        for state in self.states.iter() {
+            if state.transitions.len() != self.input_symbols.len() {
+                diags.push(RowLength {
+                    state: state.name.clone(),
+                    expected: self.input_symbols.len(),
+                    found: state.transitions.len()
+                });
+            }
+
+            if state.is_final() {
+                if state.transitions.iter().any(|t| t.is_some()) {
+                    diags.push(FinalTransitions(state.name.clone()));
+                }
+                continue;
+            }
+
+            let pairs = state.transitions.iter().zip(self.input_symbols.iter());
+            for (trans, &symbol) in pairs {
+                let trans = match *trans {
+                    Some(ref trans) => trans,
+                    None => continue
+                };
+                if self.find_state(&trans.state).is_none() {
+                    diags.push(UnknownState {
+                        state: state.name.clone(),
+                        symbol: symbol,
+                        target: trans.state.clone()
+                    });
+                }
+                if !self.input_symbols.contains(&trans.symbol) {
+                    diags.push(UnknownSymbol {
+                        state: state.name.clone(),
+                        symbol: symbol,
+                        written: trans.symbol
+                    });
+                }
+            }
+        }
+
+        if !self.states.is_empty() && !self.states.iter().any(|s| s.is_final()) {
+            diags.push(NoFinalState);
+        }
+
+        let reachable = self.reachable_states();
+        for (state, &reachable) in self.states.iter().zip(reachable.iter()) {
+            if !reachable {
+                diags.push(Unreachable(state.name.clone()));
+            }
+        }
+
+        diags
+    }
+
+    /// For each state, whether it can be reached from the initial state.
+    pub fn reachable_states(&self) -> Vec<bool> {
+        let mut reachable = vec![false; self.states.len()];
+        if self.states.is_empty() {
+            return reachable;
+        }
+
+        let mut todo = vec![0];
+        reachable[0] = true;
+        while let Some(i) = todo.pop() {
+            let state = &self.states[i];
+            if state.is_final() {
+                continue;
+            }
+            for trans in state.transitions.iter().filter_map(|t| t.as_ref()) {
+                if let Some(j) = self.find_state(&trans.state) {
+                    if !reachable[j] {
+                        reachable[j] = true;
+                        todo.push(j);
+                    }
+                }
+            }
+        }
+
+        reachable
+    }
+}
@@ -0,0 +1,104 @@
+//! Writing a `TMDesc` in the TM file format, so that generated or
+//! transformed machines can be saved.
+
+use std::fmt::{self, Display, Formatter};
+use {TMDesc, HaltKind, ExportError, Format, SAME};
+
+impl TMDesc {
+    /// The final states that a file without directives would have, i.e. a
+    /// `STOPP` state that halts.
+    fn has_default_halting(&self) -> bool {
+        self.states.iter().all(|s| {
+            if s.name == "STOPP" {
+                s.halt == Some(HaltKind::Halt)
+            } else {
+                s.halt.is_none()
+            }
+        })
+    }
+
+    /// Write the machine as a TM file, like `to_string`. This fails if a
+    /// symbol or state is called `=`, which a TM file would read as the
+    /// shorthand for the same symbol or state.
+    pub fn to_tm(&self) -> Result<String, ExportError> {
+        let symbols = self.input_symbols.iter().map(|c| c.to_string());
+        let names = self.states.iter().map(|s| s.name.clone());
+        match symbols.chain(names).find(|name| name == SAME) {
+            Some(name) => Err(ExportError {
+                format: Format::Tm,
+                reason: format!("`{}' can't be a symbol or state name", name)
+            }),
+            None => Ok(self.to_string())
+        }
+    }
+}
+
+/// Writes the description as a canonical TM file, which `from_string` reads
+/// back as the same machine. The columns are padded with spaces, so that
+/// they line up. Halting states are declared with directives at the end,
+/// unless the only one is a `STOPP` state.
+///
+/// Symbols and states called `=` are written as they are, so use `to_tm` to
+/// catch machines that can't be read back.
+impl Display for TMDesc {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let mut rows = vec![];
+        let mut header = vec![String::new()];
+        header.extend(self.input_symbols.iter().map(|c| c.to_string()));
+        rows.push(header);
+
+        for state in self.states.iter() {
+            let mut row = vec![state.name.clone()];
+            // States without any transitions get a row of their own.
+            if state.transitions.iter().any(|t| t.is_some()) {
+                row.extend(state.transitions.iter().map(|t| match *t {
+                    Some(ref t) => format!("{},{},{}", t.state, t.symbol, t.movement.to_char()),
+                    None => "-".into()
+                }));
+            }
+            rows.push(row);
+        }
+
+        let columns = rows.iter().map(|row| row.len()).max().unwrap_or(0);
+        let widths = (0..columns).map(|i| {
+            rows.iter().filter_map(|row| row.get(i)).map(|w| w.chars().count())
+                .max().unwrap_or(0)
+        }).collect::<Vec<_>>();
+
+        for row in rows.iter() {
+            let mut line = String::new();
+            for (i, word) in row.iter().enumerate() {
+                if i > 0 {
+                    line.push('\t');
+                }
+                line.push_str(word);
+                if i + 1 < row.len() {
+                    for _ in word.chars().count()..widths[i] {
+                        line.push(' ');
+                    }
+                }
+            }
+            try!(writeln!(f, "{}", line));
+        }
+
+        if self.has_default_halting() {
+            return Ok(());
+        }
+        let kinds = [HaltKind::Halt, HaltKind::Accept, HaltKind::Reject];
+        let mut any = false;
+        for &kind in kinds.iter() {
+            let names = self.states.iter().filter(|s| s.halt == Some(kind))
+                .map(|s| &s.name[..]).collect::<Vec<_>>();
+            if !names.is_empty() {
+                try!(writeln!(f, "{}\t{}", kind.directive(), names.join("\t")));
+                any = true;
+            }
+        }
+        // Without final states, a directive is still needed to stop `STOPP`
+        // from halting.
+        if !any {
+            try!(writeln!(f, "{}", HaltKind::Halt.directive()));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,268 @@
+//! A minimal XML parser, enough for the files that JFLAP writes: elements,
+//! attributes, text, comments, character references and CDATA sections.
+//! Namespaces and DTDs are not supported.
+
+/// An element, with its children and the text directly inside it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Element {
+    pub name: String,
+    pub attributes: Vec<(String, String)>,
+    pub children: Vec<Element>,
+    pub text: String,
+    /// The position of the `<` of the start tag
+    pub line: usize,
+    pub column: usize
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct XmlError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String
+}
+
+impl Element {
+    pub fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes.iter().find(|&&(ref n, _)| n == name).map(|&(_, ref v)| &v[..])
+    }
+
+    /// The first child with the given name
+    pub fn child(&self, name: &str) -> Option<&Element> {
+        self.children.iter().find(|c| c.name == name)
+    }
+}
+
+/// Parse a document, and return its root element.
+pub fn parse(s: &str) -> Result<Element, XmlError> {
+    let mut parser = Parser { chars: s.chars().collect(), pos: 0, line: 1, column: 1 };
+    try!(parser.misc());
+    if parser.peek() != Some('<') {
+        return Err(parser.error("expected an element"));
+    }
+    let root = try!(parser.element());
+    try!(parser.misc());
+    if parser.peek().is_some() {
+        return Err(parser.error("unexpected content after the root element"));
+    }
+    Ok(root)
+}
+
+/// Escape text for use in an element or an attribute.
+pub fn escape(s: &str) -> String {
+    let mut escaped = String::new();
+    for c in s.chars() {
+        match c {
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '&' => escaped.push_str("&amp;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c)
+        }
+    }
+    escaped
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+    line: usize,
+    column: usize
+}
+
+impl Parser {
+    fn error(&self, message: &str) -> XmlError {
+        XmlError { line: self.line, column: self.column, message: message.into() }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).cloned()
+    }
+
+    fn looking_at(&self, s: &str) -> bool {
+        s.chars().enumerate().all(|(i, c)| self.chars.get(self.pos + i) == Some(&c))
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek();
+        if let Some(c) = c {
+            self.pos += 1;
+            if c == '\n' {
+                self.line += 1;
+                self.column = 1;
+            } else {
+                self.column += 1;
+            }
+        }
+        c
+    }
+
+    fn skip(&mut self, n: usize) {
+        for _ in 0..n {
+            self.next();
+        }
+    }
+
+    fn expect(&mut self, s: &str) -> Result<(), XmlError> {
+        if self.looking_at(s) {
+            self.skip(s.chars().count());
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected `{}'", s)))
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().map_or(false, |c| c.is_whitespace()) {
+            self.next();
+        }
+    }
+
+    /// Skip everything up to and including `end`.
+    fn skip_past(&mut self, end: &str) -> Result<(), XmlError> {
+        while !self.looking_at(end) {
+            if self.next().is_none() {
+                return Err(self.error(&format!("expected `{}'", end)));
+            }
+        }
+        self.skip(end.chars().count());
+        Ok(())
+    }
+
+    /// Whitespace, comments, processing instructions and doctypes
+    fn misc(&mut self) -> Result<(), XmlError> {
+        loop {
+            self.skip_whitespace();
+            if self.looking_at("<!--") {
+                try!(self.skip_past("-->"));
+            } else if self.looking_at("<?") {
+                try!(self.skip_past("?>"));
+            } else if self.looking_at("<!DOCTYPE") {
+                try!(self.skip_past(">"));
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    fn name(&mut self) -> Result<String, XmlError> {
+        let mut name = String::new();
+        while let Some(c) = self.peek() {
+            if c.is_alphanumeric() || c == '_' || c == '-' || c == '.' || c == ':' {
+                name.push(c);
+                self.next();
+            } else {
+                break;
+            }
+        }
+        if name.is_empty() {
+            return Err(self.error("expected a name"));
+        }
+        Ok(name)
+    }
+
+    /// A character or entity reference, after the `&`
+    fn reference(&mut self) -> Result<char, XmlError> {
+        let mut name = String::new();
+        loop {
+            match self.next() {
+                Some(';') => break,
+                Some(c) => name.push(c),
+                None => return Err(self.error("unterminated reference"))
+            }
+        }
+        let c = match &name[..] {
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "amp" => Some('&'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ if name.starts_with("#x") => u32::from_str_radix(&name[2..], 16).ok()
+                .and_then(::std::char::from_u32),
+            _ if name.starts_with("#") => name[1..].parse().ok().and_then(::std::char::from_u32),
+            _ => None
+        };
+        c.ok_or_else(|| self.error(&format!("unknown reference `&{};'", name)))
+    }
+
+    fn element(&mut self) -> Result<Element, XmlError> {
+        let (line, column) = (self.line, self.column);
+        try!(self.expect("<"));
+        let name = try!(self.name());
+        let mut element = Element {
+            name: name, attributes: vec![], children: vec![], text: String::new(),
+            line: line, column: column
+        };
+
+        loop {
+            self.skip_whitespace();
+            if self.looking_at("/>") {
+                self.skip(2);
+                return Ok(element);
+            }
+            if self.looking_at(">") {
+                self.skip(1);
+                break;
+            }
+            let attribute = try!(self.name());
+            self.skip_whitespace();
+            try!(self.expect("="));
+            self.skip_whitespace();
+            let quote = match self.next() {
+                Some(q) if q == '"' || q == '\'' => q,
+                _ => return Err(self.error("expected a quoted value"))
+            };
+            let mut value = String::new();
+            loop {
+                match self.next() {
+                    Some(c) if c == quote => break,
+                    Some('&') => value.push(try!(self.reference())),
+                    Some(c) => value.push(c),
+                    None => return Err(self.error("unterminated attribute value"))
+                }
+            }
+            element.attributes.push((attribute, value));
+        }
+
+        loop {
+            if self.looking_at("</") {
+                self.skip(2);
+                let (line, column) = (self.line, self.column);
+                let end = try!(self.name());
+                if end != element.name {
+                    return Err(XmlError {
+                        line: line, column: column,
+                        message: format!("expected `</{}>'", element.name)
+                    });
+                }
+                self.skip_whitespace();
+                try!(self.expect(">"));
+                return Ok(element);
+            } else if self.looking_at("<!--") {
+                try!(self.skip_past("-->"));
+            } else if self.looking_at("<![CDATA[") {
+                self.skip(9);
+                while !self.looking_at("]]>") {
+                    match self.next() {
+                        Some(c) => element.text.push(c),
+                        None => return Err(self.error("unterminated CDATA section"))
+                    }
+                }
+                self.skip(3);
+            } else if self.looking_at("<?") {
+                try!(self.skip_past("?>"));
+            } else if self.looking_at("<") {
+                element.children.push(try!(self.element()));
+            } else {
+                match self.next() {
+                    Some('&') => {
+                        let c = try!(self.reference());
+                        element.text.push(c);
+                    }
+                    Some(c) => element.text.push(c),
+                    None => return Err(self.error(&format!("expected `</{}>'", element.name)))
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,301 @@
+//! A parser for the small subset of YAML that turingmachine.io documents use:
+//! block mappings, plain and quoted scalars, and flow sequences and mappings
+//! like `[0, ' ']` and `{write: 1, L: done}`. Block sequences, anchors, tags
+//! and multi-line scalars are not supported.
+
+/// A parsed document. Scalars are kept as text, so `0` is `Scalar("0")`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Yaml {
+    Null,
+    Scalar(String),
+    Seq(Vec<Yaml>),
+    Map(Vec<Entry>)
+}
+
+/// A key and its value, with the position of the key in the source.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+    pub key: Yaml,
+    pub value: Yaml,
+    pub line: usize,
+    pub column: usize
+}
+
+/// A syntax error at a line and column
+#[derive(Debug, Clone, PartialEq)]
+pub struct YamlError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String
+}
+
+/// A line without the comment and the indentation
+struct Line {
+    number: usize,
+    indent: usize,
+    text: Vec<char>
+}
+
+impl Yaml {
+    pub fn as_str(&self) -> Option<&str> {
+        match *self {
+            Yaml::Scalar(ref s) => Some(s),
+            _ => None
+        }
+    }
+
+    /// Look up a key of a mapping.
+    pub fn get(&self, key: &str) -> Option<&Entry> {
+        match *self {
+            Yaml::Map(ref entries) => entries.iter().find(|e| e.key.as_str() == Some(key)),
+            _ => None
+        }
+    }
+}
+
+pub fn parse(s: &str) -> Result<Yaml, YamlError> {
+    let mut lines = vec![];
+    for (i, line) in s.lines().enumerate() {
+        let chars = strip_comment(line);
+        let indent = chars.iter().take_while(|&&c| c == ' ').count();
+        if chars.get(indent) == Some(&'\t') {
+            return Err(YamlError {
+                line: i + 1, column: indent + 1,
+                message: "tabs can't be used for indentation".into()
+            });
+        }
+        // Document markers are allowed, but there's only one document.
+        let text = chars[indent..].to_vec();
+        if text.is_empty() || text.iter().cloned().collect::<String>() == "---" {
+            continue;
+        }
+        lines.push(Line { number: i + 1, indent: indent, text: text });
+    }
+
+    if lines.is_empty() {
+        return Ok(Yaml::Null);
+    }
+    let mut pos = 0;
+    let indent = lines[0].indent;
+    let document = try!(block_map(&lines, &mut pos, indent));
+    match lines.get(pos) {
+        Some(line) => Err(YamlError {
+            line: line.number, column: line.indent + 1, message: "bad indentation".into()
+        }),
+        None => Ok(document)
+    }
+}
+
+/// The characters of a line, without a comment and trailing whitespace. A
+/// comment starts with `#` at the start or after whitespace, outside of
+/// quotes.
+fn strip_comment(line: &str) -> Vec<char> {
+    let mut chars = vec![];
+    let mut quote = None;
+    for c in line.chars() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => (),
+            None if c == '\'' || c == '"' => quote = Some(c),
+            None if c == '#' && chars.last().map_or(true, |c: &char| c.is_whitespace()) => break,
+            None => ()
+        }
+        chars.push(c);
+    }
+    while chars.last().map_or(false, |c| c.is_whitespace()) {
+        chars.pop();
+    }
+    chars
+}
+
+/// Parse the lines of a mapping that are indented by `indent`.
+fn block_map(lines: &[Line], pos: &mut usize, indent: usize) -> Result<Yaml, YamlError> {
+    let mut entries = vec![];
+    while *pos < lines.len() && lines[*pos].indent == indent {
+        let line = &lines[*pos];
+        *pos += 1;
+        let mut parser = Parser { text: &line.text, pos: 0, line: line.number, indent: indent };
+        if line.text[0] == '-' && line.text.get(1).map_or(true, |&c| c == ' ') {
+            return Err(parser.error("sequences are not supported"));
+        }
+
+        let key = try!(parser.key(false));
+        let column = indent + 1;
+        parser.skip_spaces();
+        if !parser.eat(':') {
+            return Err(parser.error("expected `:'"));
+        }
+        parser.skip_spaces();
+
+        let value = if parser.at_end() {
+            match lines.get(*pos) {
+                Some(next) if next.indent > indent => try!(block_map(lines, pos, next.indent)),
+                _ => Yaml::Null
+            }
+        } else {
+            let value = try!(parser.value(false));
+            parser.skip_spaces();
+            if !parser.at_end() {
+                return Err(parser.error("unexpected characters after the value"));
+            }
+            value
+        };
+
+        entries.push(Entry { key: key, value: value, line: line.number, column: column });
+    }
+    Ok(Yaml::Map(entries))
+}
+
+struct Parser<'a> {
+    text: &'a [char],
+    pos: usize,
+    line: usize,
+    /// The column of `text[0]`, minus 1
+    indent: usize
+}
+
+impl<'a> Parser<'a> {
+    fn error(&self, message: &str) -> YamlError {
+        YamlError { line: self.line, column: self.indent + self.pos + 1, message: message.into() }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.text.get(self.pos).cloned()
+    }
+
+    fn at_end(&self) -> bool {
+        self.pos >= self.text.len()
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn skip_spaces(&mut self) {
+        while self.peek() == Some(' ') {
+            self.pos += 1;
+        }
+    }
+
+    /// Whether there is a `:` that separates a key from a value.
+    fn at_colon(&self, flow: bool) -> bool {
+        self.peek() == Some(':') && match self.text.get(self.pos + 1) {
+            None | Some(&' ') => true,
+            Some(&c) => flow && (c == ',' || c == '}' || c == ']')
+        }
+    }
+
+    /// A key, which is a scalar or a flow sequence.
+    fn key(&mut self, flow: bool) -> Result<Yaml, YamlError> {
+        match self.peek() {
+            Some('[') => self.value(flow),
+            Some('{') => Err(self.error("mappings can't be keys")),
+            _ => self.scalar(flow)
+        }
+    }
+
+    fn value(&mut self, flow: bool) -> Result<Yaml, YamlError> {
+        self.skip_spaces();
+        match self.peek() {
+            Some('[') => {
+                self.pos += 1;
+                let mut items = vec![];
+                loop {
+                    self.skip_spaces();
+                    if self.eat(']') {
+                        return Ok(Yaml::Seq(items));
+                    }
+                    items.push(try!(self.value(true)));
+                    self.skip_spaces();
+                    if !self.eat(',') && self.peek() != Some(']') {
+                        return Err(self.error("expected `,' or `]'"));
+                    }
+                }
+            }
+            Some('{') => {
+                self.pos += 1;
+                let mut entries = vec![];
+                loop {
+                    self.skip_spaces();
+                    if self.eat('}') {
+                        return Ok(Yaml::Map(entries));
+                    }
+                    let column = self.indent + self.pos + 1;
+                    let key = try!(self.key(true));
+                    self.skip_spaces();
+                    // A key without a value, like `{write: 1, L}`, maps to null.
+                    let value = if self.eat(':') {
+                        try!(self.value(true))
+                    } else {
+                        Yaml::Null
+                    };
+                    entries.push(Entry { key: key, value: value, line: self.line, column: column });
+                    self.skip_spaces();
+                    if !self.eat(',') && self.peek() != Some('}') {
+                        return Err(self.error("expected `,' or `}'"));
+                    }
+                }
+            }
+            _ => self.scalar(flow)
+        }
+    }
+
+    /// A quoted or plain scalar. Plain scalars in flow collections end at
+    /// `,`, `]` and `}`, and keys end at the `:`.
+    fn scalar(&mut self, flow: bool) -> Result<Yaml, YamlError> {
+        self.skip_spaces();
+        match self.peek() {
+            Some(q) if q == '\'' || q == '"' => {
+                self.pos += 1;
+                let mut s = String::new();
+                loop {
+                    let c = match self.peek() {
+                        Some(c) => c,
+                        None => return Err(self.error("unterminated string"))
+                    };
+                    self.pos += 1;
+                    if c == q {
+                        // '' is an escaped ' in single quotes.
+                        if q == '\'' && self.peek() == Some('\'') {
+                            self.pos += 1;
+                        } else {
+                            return Ok(Yaml::Scalar(s));
+                        }
+                    } else if c == '\\' && q == '"' {
+                        let escaped = match self.peek() {
+                            Some('n') => '\n',
+                            Some('t') => '\t',
+                            Some(c) => c,
+                            None => return Err(self.error("unterminated string"))
+                        };
+                        self.pos += 1;
+                        s.push(escaped);
+                        continue;
+                    }
+                    s.push(c);
+                }
+            }
+            _ => {
+                let start = self.pos;
+                while let Some(c) = self.peek() {
+                    if self.at_colon(flow) || (flow && (c == ',' || c == ']' || c == '}')) {
+                        break;
+                    }
+                    self.pos += 1;
+                }
+                let s = self.text[start..self.pos].iter().cloned().collect::<String>();
+                let s = s.trim_right();
+                if s.is_empty() || s == "~" || s == "null" {
+                    Ok(Yaml::Null)
+                } else {
+                    Ok(Yaml::Scalar(s.into()))
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,82 @@
+extern crate turing_machines;
+
+use turing_machines::{TMDesc, TM, StepOutcome, HaltKind, Format, TMDescError};
+
+/// Run a machine on the blank tape, and count the steps and the 1s left.
+fn run(desc: &TMDesc) -> (StepOutcome, u64, usize) {
+    let mut tm = TM::new(desc, "").unwrap();
+    loop {
+        match tm.run_step().unwrap() {
+            StepOutcome::Continue => (),
+            outcome => {
+                let ones = tm.tape().to_string().chars().filter(|&c| c == '1').count();
+                return (outcome, tm.steps(), ones);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_champions() {
+    let bb2 = TMDesc::from_busy_beaver("1RB1LB_1LA1RZ").unwrap();
+    assert_eq!(bb2.input_symbols, vec!['1', '0']);
+    assert_eq!(bb2.states.iter().map(|s| &s.name[..]).collect::<Vec<_>>(),
+               vec!["A", "B", "Z"]);
+    assert_eq!(bb2.states[2].halt, Some(HaltKind::Halt));
+    assert_eq!(run(&bb2), (StepOutcome::Halted(HaltKind::Halt), 6, 4));
+
+    let bb3 = TMDesc::from_string_in(Format::BusyBeaver, "1RB1RZ_1LB0RC_1LC1LA\n").unwrap();
+    assert_eq!(run(&bb3), (StepOutcome::Halted(HaltKind::Halt), 21, 5));
+}
+
+#[test]
+fn test_round_trip() {
+    for &bb in ["1RB1LB_1LA1RZ", "1RB2LA1RA_1LB1LZ2RA", "1RB---_0LA1RZ"].iter() {
+        let desc = TMDesc::from_busy_beaver(bb).unwrap();
+        assert_eq!(desc.to_busy_beaver().unwrap(), bb);
+    }
+
+    // `-` and other letters are read as halting, but written as `Z`.
+    let desc = TMDesc::from_busy_beaver("1RB1LH_1LA1R-").unwrap();
+    assert_eq!(desc.states.len(), 4);
+    assert_eq!(desc.to_busy_beaver().unwrap(), "1RB1LZ_1LA1RZ");
+
+    // The blank becomes 0, and the other symbols are numbered in order.
+    let desc = TMDesc::from_string("
+	x	B
+q0	q1,x,R	q1,x,L
+q1	q0,x,L	STOPP,x,R
+STOPP
+").unwrap();
+    assert_eq!(desc.to_busy_beaver().unwrap(), "1LB1RB_1RZ1LA");
+}
+
+#[test]
+fn test_export_errors() {
+    let desc = TMDesc::from_string("
+	1	0
+q0	STOPP,0,N	-
+STOPP
+").unwrap();
+    assert_eq!(desc.to_busy_beaver().unwrap_err().to_string(),
+               "the machine can't be written in busy beaver format: \
+                state q0 doesn't move on `1'");
+}
+
+fn error(bb: &str) -> String {
+    match TMDesc::from_busy_beaver(bb) {
+        Err(e @ TMDescError::Malformed { .. }) => e.to_string(),
+        other => panic!("unexpected result {:?}", other)
+    }
+}
+
+#[test]
+fn test_import_errors() {
+    assert_eq!(error("1RB1LB_1LA1R"),
+               "1:8: state B has 5 characters, but should have 6 (in busy beaver format)");
+    assert_eq!(error("  1RB1LB_1LA1NZ"),
+               "1:14: `N' is not a valid movement (L or R) (in busy beaver format)");
+    assert_eq!(error("1RB2LB_1LA1RZ"), "1:4: `2' is not a symbol (in busy beaver format)");
+    assert_eq!(error("1RB1LB\n1LA1RZ"),
+               "2:1: there is more than one machine (in busy beaver format)");
+}
@@ -0,0 +1,119 @@
+extern crate rustc_serialize;
+extern crate turing_machines;
+
+use rustc_serialize::json::ToJson;
+use turing_machines::{TMDesc, TM, FastTM, MacroTM, Configuration, ConfigurationError};
+use turing_machines::{StepOutcome, HaltKind};
+
+static HELLO_TM: &'static str = include_str!("../data/hello.tm");
+
+#[test]
+fn test_resume() {
+    let desc = TMDesc::from_string(HELLO_TM).unwrap();
+    let mut tm = TM::new(&desc, "").unwrap();
+    for _ in 0..10 {
+        tm.run_step().unwrap();
+    }
+
+    let saved = tm.configuration().to_json().to_string();
+    let configuration = Configuration::from_string(&saved).unwrap();
+    assert_eq!(configuration, tm.configuration());
+    assert_eq!(configuration.steps, 10);
+
+    let mut resumed = TM::from_configuration(&desc, &configuration).unwrap();
+    while tm.run_step().unwrap() == StepOutcome::Continue {
+        assert_eq!(resumed.run_step().unwrap(), StepOutcome::Continue);
+    }
+    assert!(resumed.run_step().unwrap() != StepOutcome::Continue);
+    assert_eq!(resumed.configuration(), tm.configuration());
+}
+
+#[test]
+fn test_resume_fast() {
+    // Runs left over the input, so that the tape starts before position 0.
+    let desc = TMDesc::from_string("
+	0	1	B
+left	left,1,L	left,0,L	right,B,R
+right	right,0,R	right,1,R	STOPP,1,N
+STOPP
+").unwrap();
+    let mut tm = TM::new(&desc, "0110").unwrap();
+    let mut configurations = vec![];
+    loop {
+        configurations.push(tm.configuration());
+        if tm.run_step().unwrap() != StepOutcome::Continue {
+            break;
+        }
+    }
+    let last = tm.configuration();
+    assert_eq!((last.tape_start, &last.tape[..]), (-1, "B11101"));
+
+    for configuration in configurations.iter() {
+        let mut fast = FastTM::from_configuration(&desc, configuration).unwrap();
+        assert_eq!(fast.run(None).unwrap(), StepOutcome::Halted(HaltKind::Halt));
+        assert_eq!(Configuration::new(&fast.state().name, fast.head(), &fast.tape(),
+                                      fast.steps()), last);
+
+        for block_size in 1..4 {
+            let mut tm = MacroTM::from_configuration(&desc, configuration, block_size).unwrap();
+            assert_eq!(tm.run(None).unwrap(), StepOutcome::Halted(HaltKind::Halt));
+            assert_eq!(Configuration::new(&tm.state().name, tm.head(), &tm.tape(), tm.steps()),
+                       last);
+        }
+    }
+}
+
+#[test]
+fn test_errors() {
+    let desc = TMDesc::from_string(HELLO_TM).unwrap();
+
+    match Configuration::from_string(r#"{"state": "q0", "head": 0}"#) {
+        Err(ConfigurationError::BadField("tape")) => (),
+        other => panic!("unexpected result {:?}", other)
+    }
+
+    let configuration = Configuration::from_string(
+        r#"{"state": "nope", "head": 0, "tape": "", "tape_start": 0, "steps": 0}"#).unwrap();
+    match TM::from_configuration(&desc, &configuration) {
+        Err(ConfigurationError::UnknownState(ref name)) if name == "nope" => (),
+        Err(e) => panic!("unexpected error {}", e),
+        Ok(_) => panic!("unknown state accepted")
+    }
+
+    // The head must be on the tape, or right next to it.
+    let configuration = |head: isize| Configuration {
+        state: "q0".into(), head: head, tape: "HE".into(), tape_start: -1, steps: 0
+    };
+    for &head in [-2, -1, 0, 1].iter() {
+        assert!(TM::from_configuration(&desc, &configuration(head)).is_ok());
+    }
+    for &head in [-3, 2, 1 << 40].iter() {
+        match FastTM::from_configuration(&desc, &configuration(head)) {
+            Err(ConfigurationError::HeadOutOfRange(h)) => assert_eq!(h, head),
+            Err(e) => panic!("unexpected error {}", e),
+            Ok(_) => panic!("head {} accepted", head)
+        }
+        assert!(TM::from_configuration(&desc, &configuration(head)).is_err());
+        assert!(MacroTM::from_configuration(&desc, &configuration(head), 2).is_err());
+    }
+
+    // So must position 0, or a file could make the tape huge.
+    let configuration = |tape_start: isize| Configuration {
+        state: "q0".into(), head: tape_start, tape: "HE".into(), tape_start: tape_start,
+        steps: 0
+    };
+    for &start in [-3, -2, 1].iter() {
+        assert!(TM::from_configuration(&desc, &configuration(start)).is_ok());
+    }
+    let huge = Configuration::from_string(r#"{"state": "q0", "head": 1099511627776,
+        "tape": "", "tape_start": 1099511627776, "steps": 0}"#).unwrap();
+    for &config in [&configuration(-4), &configuration(2), &huge].iter() {
+        match FastTM::from_configuration(&desc, config) {
+            Err(ConfigurationError::TapeOutOfRange(start)) => assert_eq!(start, config.tape_start),
+            Err(e) => panic!("unexpected error {}", e),
+            Ok(_) => panic!("tape at {} accepted", config.tape_start)
+        }
+        assert!(TM::from_configuration(&desc, config).is_err());
+        assert!(MacroTM::from_configuration(&desc, config, 2).is_err());
+    }
+}
@@ -0,0 +1,44 @@
+extern crate turing_machines;
+
+use turing_machines::{TMDesc, TM, Debugger, Breakpoint, Stop, StepOutcome, HaltKind};
+
+static HELLO_TM: &'static str = include_str!("../data/hello.tm");
+
+#[test]
+fn test_breakpoints() {
+    let desc = TMDesc::from_string(HELLO_TM).unwrap();
+    let mut debugger = Debugger::new(TM::new(&desc, "").unwrap());
+
+    let back = desc.find_state("q←").unwrap();
+    debugger.add_breakpoint(Breakpoint::StateSymbol(back, 'W'));
+    debugger.add_breakpoint(Breakpoint::Step(20));
+
+    match debugger.cont() {
+        Stop::Breakpoint(0) => assert_eq!(debugger.tm().head(), 6),
+        other => panic!("unexpected stop: {:?}", other)
+    }
+    match debugger.step(100) {
+        Stop::Breakpoint(1) => assert_eq!(debugger.tm().steps(), 20),
+        other => panic!("unexpected stop: {:?}", other)
+    }
+    match debugger.cont() {
+        Stop::Finished(StepOutcome::Halted(HaltKind::Halt)) => (),
+        other => panic!("unexpected stop: {:?}", other)
+    }
+}
+
+#[test]
+fn test_commands() {
+    let desc = TMDesc::from_string(HELLO_TM).unwrap();
+    let mut debugger = Debugger::new(TM::new(&desc, "").unwrap());
+    let mut output = vec![];
+
+    let commands = "break head 3\ncontinue\ntape 2\nquit\nstep\n";
+    debugger.repl(commands.as_bytes(), &mut output).unwrap();
+
+    let output = String::from_utf8(output).unwrap();
+    assert!(output.contains("breakpoint 0: head at 3"));
+    assert!(output.contains("step 3, state q3, head at 3, reading 'B'"));
+    assert!(output.contains("…EL[q3]BBB\n"));
+    assert_eq!(debugger.tm().steps(), 3);
+}
@@ -0,0 +1,79 @@
+extern crate turing_machines;
+
+use turing_machines::{TMDesc, TM, RunOutcome, NonHaltingReason, HaltKind};
+
+static HELLO_TM: &'static str = include_str!("../data/hello.tm");
+
+// Goes back and forth between two cells forever.
+static PING_PONG_TM: &'static str = "
+	1	B
+q0	q1,1,R	q1,1,R
+q1	q0,1,L	q0,1,L
+";
+
+// Writes 101010... to the right forever, after going back over the input.
+static STRIPES_TM: &'static str = "
+	0	1	B
+back	back,0,L	back,1,L	q0,B,R
+q0	q0,0,R	q0,1,R	q1,1,R
+q1	q1,0,R	q1,1,R	q0,0,R
+";
+
+// Fills the tape with 1s in both directions, going back and forth.
+static BOUNCER_TM: &'static str = "
+	1	B
+q0	q0,1,R	q1,1,L
+q1	q1,1,L	q0,1,R
+";
+
+#[test]
+fn test_cycle() {
+    let desc = TMDesc::from_string(PING_PONG_TM).unwrap();
+    let mut tm = TM::new(&desc, "").unwrap();
+    assert_eq!(tm.run_until(1000).unwrap(),
+               RunOutcome::NonHalting { reason: NonHaltingReason::Cycle, period: 2 });
+    assert!(tm.steps() < 10);
+}
+
+#[test]
+fn test_translated_cycle() {
+    let desc = TMDesc::from_string(STRIPES_TM).unwrap();
+    let mut tm = TM::new(&desc, "0110").unwrap();
+    match tm.run_until(1000).unwrap() {
+        RunOutcome::NonHalting { reason, period } => {
+            assert_eq!(reason, NonHaltingReason::TranslatedCycle);
+            assert_eq!(period, 2);
+        }
+        outcome => panic!("unexpected outcome {:?}", outcome)
+    }
+}
+
+#[test]
+fn test_no_false_positives() {
+    let desc = TMDesc::from_string(BOUNCER_TM).unwrap();
+    let mut tm = TM::new(&desc, "").unwrap();
+    assert_eq!(tm.run_until(10000).unwrap(), RunOutcome::LimitReached);
+    assert_eq!(tm.steps(), 10000);
+
+    let desc = TMDesc::from_string(HELLO_TM).unwrap();
+    let mut tm = TM::new(&desc, "").unwrap();
+    assert_eq!(tm.run_until(1000).unwrap(), RunOutcome::Halted(HaltKind::Halt));
+}
+
+// Moves right forever, but goes back to overwrite every cell once.
+static BACKTRACK_TM: &'static str = "
+	A	C	B
+q0	-	-	q1,A,R
+q1	-	-	q2,B,L
+q2	q0,C,R	-	-
+";
+
+#[test]
+fn test_translated_cycle_with_overwrites() {
+    let desc = TMDesc::from_string(BACKTRACK_TM).unwrap();
+    let mut tm = TM::new(&desc, "").unwrap();
+    assert_eq!(tm.run_until(1000).unwrap(),
+               RunOutcome::NonHalting { reason: NonHaltingReason::TranslatedCycle, period: 3 });
+    assert!(tm.steps() < 20);
+    assert_eq!(tm.tape().to_trimmed_string(), "CA");
+}
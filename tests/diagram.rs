@@ -0,0 +1,66 @@
+extern crate turing_machines;
+
+use turing_machines::{TMDesc, TM, SpaceTime, DiagramFormat, StepOutcome};
+
+static HELLO_TM: &'static str = include_str!("../data/hello.tm");
+
+fn record(every: u64, color_states: bool) -> (TMDesc, SpaceTime) {
+    let desc = TMDesc::from_string(HELLO_TM).unwrap();
+    let mut diagram = SpaceTime::new(every, color_states);
+    {
+        let mut tm = TM::new(&desc, "").unwrap();
+        diagram.record(&tm);
+        while tm.run_step().unwrap() == StepOutcome::Continue {
+            diagram.record(&tm);
+        }
+        diagram.record(&tm);
+    }
+    (desc, diagram)
+}
+
+#[test]
+fn test_pbm() {
+    let (desc, diagram) = record(1, false);
+    let mut out = vec![];
+    diagram.write(&desc, DiagramFormat::Pbm, &mut out).unwrap();
+
+    let header = format!("P4\n13 {}\n", diagram.len());
+    assert!(out.starts_with(header.as_bytes()));
+    // The tape goes from -1 to 11, which takes two bytes per row. The
+    // first row is blank, the second has an H.
+    assert_eq!(out.len(), header.len() + 2 * diagram.len());
+    assert_eq!(&out[header.len()..header.len() + 4], &[0, 0, 0x40, 0]);
+}
+
+#[test]
+fn test_ppm_and_svg() {
+    // The head only goes to -1 in the last step, which isn't recorded.
+    let (desc, diagram) = record(5, true);
+    let mut out = vec![];
+    diagram.write(&desc, DiagramFormat::Ppm, &mut out).unwrap();
+    let header = format!("P6\n12 {}\n255\n", diagram.len());
+    assert!(out.starts_with(header.as_bytes()));
+    assert_eq!(out.len(), header.len() + 3 * 12 * diagram.len());
+
+    let mut out = vec![];
+    diagram.write(&desc, DiagramFormat::Svg, &mut out).unwrap();
+    let svg = String::from_utf8(out).unwrap();
+    assert!(svg.starts_with("<svg "));
+    assert!(svg.contains("<rect x=\"0\" y=\"0\" width=\"1\" height=\"1\" fill=\"#c80000\"/>"));
+    assert!(svg.ends_with("</svg>\n"));
+}
+
+#[test]
+fn test_max_rows() {
+    // This machine runs to the right forever.
+    let desc = TMDesc::from_string("\tB\nq0\tq0,B,R\n").unwrap();
+    let mut diagram = SpaceTime::new(1, false);
+    let mut tm = TM::new(&desc, "").unwrap();
+    diagram.record(&tm);
+    for _ in 0..10000 {
+        tm.run_step().unwrap();
+        diagram.record(&tm);
+    }
+    assert_eq!(diagram.every(), 4);
+    assert_eq!(diagram.len(), 10000 / 4 + 1);
+}
@@ -0,0 +1,33 @@
+extern crate turing_machines;
+
+use turing_machines::TMDesc;
+
+static PARITY_TM: &'static str = include_str!("../data/parity.tm");
+
+#[test]
+fn test_parity() {
+    let desc = TMDesc::from_string(PARITY_TM).unwrap();
+    let dot = desc.to_dot();
+    assert!(dot.starts_with("digraph tm {\n"));
+    assert!(dot.contains("    start -> s0;\n"));
+    assert!(dot.contains("    s0 [label=\"even\"];\n"));
+    assert!(dot.contains("    s2 [label=\"ACCEPT\", shape=doublecircle];\n"));
+    assert!(dot.contains("    s0 -> s1 [label=\"1/1,R\"];\n"));
+    assert!(dot.ends_with("}\n"));
+}
+
+#[test]
+fn test_merged_and_unreachable() {
+    let desc = TMDesc::from_string("
+	0	1	B
+q0	q0,1,R	q0,0,R	q\"1,B,L
+q\"1	STOPP,0,N	STOPP,0,N	-
+lost	q0,0,L	-	-
+STOPP
+").unwrap();
+    let dot = desc.to_dot();
+    assert!(dot.contains("    s0 -> s0 [label=\"0/1,R\\n1/0,R\"];\n"));
+    assert!(dot.contains("    s1 [label=\"q\\\"1\"];\n"));
+    assert!(dot.contains("    s2 [label=\"lost\", color=grey, fontcolor=grey];\n"));
+    assert!(dot.contains("    s2 -> s0 [label=\"0/0,L\", color=grey, fontcolor=grey];\n"));
+}
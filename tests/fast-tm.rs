@@ -0,0 +1,41 @@
+extern crate turing_machines;
+
+use turing_machines::{TMDesc, TM, FastTM, StepOutcome, HaltKind, RunErrorKind};
+
+static HELLO_TM: &'static str = include_str!("../data/hello.tm");
+static PARITY_TM: &'static str = include_str!("../data/parity.tm");
+
+#[test]
+fn test_same_as_tm() {
+    for &(machine, input) in [(HELLO_TM, ""), (PARITY_TM, "1111"), (PARITY_TM, "1")].iter() {
+        let desc = TMDesc::from_string(machine).unwrap();
+        let mut tm = TM::new(&desc, input).unwrap();
+        let mut outcome = StepOutcome::Continue;
+        while outcome == StepOutcome::Continue {
+            outcome = tm.run_step().unwrap();
+        }
+
+        let mut fast = FastTM::new(&desc, input).unwrap();
+        assert_eq!(fast.run(None).unwrap(), outcome);
+        assert_eq!(fast.steps(), tm.steps());
+        assert_eq!(fast.head(), tm.head());
+        assert_eq!(fast.state().name, tm.state().name);
+        assert_eq!(fast.tape().to_string(), tm.tape().to_string());
+        assert_eq!(fast.tape().min(), tm.tape().min());
+    }
+}
+
+#[test]
+fn test_limits_and_errors() {
+    let desc = TMDesc::from_string(HELLO_TM).unwrap();
+    let mut fast = FastTM::new(&desc, "").unwrap();
+    assert_eq!(fast.run(Some(5)).unwrap(), StepOutcome::Continue);
+    assert_eq!(fast.steps(), 5);
+    assert_eq!(fast.run(None).unwrap(), StepOutcome::Halted(HaltKind::Halt));
+
+    let desc = TMDesc::from_string(PARITY_TM).unwrap();
+    let mut fast = FastTM::new(&desc, "1x1").unwrap();
+    let err = fast.run(None).unwrap_err();
+    assert_eq!(err.kind, RunErrorKind::UnknownSymbol);
+    assert_eq!((err.head, err.symbol, err.step), (1, 'x', 1));
+}
@@ -0,0 +1,60 @@
+extern crate turing_machines;
+
+use turing_machines::{TMDesc, TMDescError, TM, StepOutcome, HaltKind};
+
+static PARITY_TM: &'static str = include_str!("../data/parity.tm");
+
+fn run(desc: &TMDesc, input: &str) -> StepOutcome {
+    let mut tm = TM::new(desc, input).unwrap();
+    loop {
+        match tm.run_step().unwrap() {
+            StepOutcome::Continue => (),
+            outcome => return outcome
+        }
+    }
+}
+
+#[test]
+fn test_accept_reject() {
+    let desc = TMDesc::from_string(PARITY_TM).unwrap();
+    assert_eq!(desc.states.len(), 4);
+    assert_eq!(desc.validate(), vec![]);
+
+    assert_eq!(run(&desc, "11"), StepOutcome::Halted(HaltKind::Accept));
+    assert_eq!(run(&desc, "111"), StepOutcome::Halted(HaltKind::Reject));
+}
+
+#[test]
+fn test_declarations_replace_stopp() {
+    let desc = TMDesc::from_string("
+	A
+q0	STOPP,A,R
+STOPP	q0,A,R
+!halt	q1
+q1
+").unwrap();
+    assert!(!desc.states[1].is_final());
+    assert_eq!(desc.states[2].halt, Some(HaltKind::Halt));
+}
+
+#[test]
+fn test_conflicting_declarations() {
+    let error = TMDesc::from_string("
+	A
+q0	ACCEPT,A,R
+!accept	ACCEPT	STOPP
+!reject	STOPP
+!halt	ACCEPT
+").unwrap_err();
+    match error {
+        TMDescError::ConflictingDirective { line, column, ref text, previous } => {
+            assert_eq!((line, column, &text[..], previous), (5, 9, "STOPP", "!accept"));
+        }
+        ref other => panic!("unexpected result: {:?}", other)
+    }
+
+    // Declaring a state twice the same way is fine.
+    let desc = TMDesc::from_string("\tA\nq0\tSTOPP,A,R\n!reject\tSTOPP\n!reject\tSTOPP\n")
+        .unwrap();
+    assert_eq!(desc.states[1].halt, Some(HaltKind::Reject));
+}
@@ -1,6 +1,6 @@
 extern crate turing_machines;
 
-use turing_machines::{TMDesc, TM};
+use turing_machines::{TMDesc, TM, StepOutcome, HaltKind};
 
 // This turing machine should write the string "HELLO.WORLD!", walk back to
 // its start and terminate.
@@ -24,15 +24,15 @@ STOPP
 
 #[test]
 fn test_hello() {
-    let desc = TMDesc::from_string(HELLO_TM);
+    let desc = TMDesc::from_string(HELLO_TM).unwrap();
     println!("{:?}", desc);
 
-    let mut tm = TM::new(&desc, "");
+    let mut tm = TM::new(&desc, "").unwrap();
 
     for _ in 0..40 {
-        tm.run_step();
+        tm.run_step().unwrap();
     }
 
-    assert!(tm.has_finished());
+    assert_eq!(tm.run_step().unwrap(), StepOutcome::Halted(HaltKind::Halt));
     assert_eq!(tm.get_tape_output(), "HELLO.WORLD!");
 }
@@ -0,0 +1,40 @@
+extern crate turing_machines;
+
+use turing_machines::{TMDesc, TM, StepOutcome, HaltKind};
+
+static HELLO_TM: &'static str = include_str!("../data/hello.tm");
+
+fn snapshot(tm: &TM) -> (u64, String, isize, String) {
+    (tm.steps(), tm.state().name.clone(), tm.head(), tm.tape().to_trimmed_string())
+}
+
+#[test]
+fn test_back_and_forth() {
+    let desc = TMDesc::from_string(HELLO_TM).unwrap();
+    let mut reference = TM::new(&desc, "").unwrap();
+    let mut snapshots = vec![snapshot(&reference)];
+    while reference.run_step().unwrap() == StepOutcome::Continue {
+        snapshots.push(snapshot(&reference));
+    }
+    snapshots.push(snapshot(&reference));
+
+    let mut tm = TM::new(&desc, "").unwrap();
+    tm.record_history(4);
+    assert_eq!(tm.goto_step(100).unwrap(), StepOutcome::Halted(HaltKind::Halt));
+    let end = tm.steps();
+
+    for n in (0..end).rev() {
+        assert!(tm.step_back());
+        assert_eq!(snapshot(&tm), snapshots[n as usize]);
+    }
+    assert!(!tm.step_back());
+
+    for &n in [13, 2, 21, 20, 7, 9, 0, 17].iter() {
+        tm.goto_step(n).unwrap();
+        assert_eq!(snapshot(&tm), snapshots[n as usize]);
+        if n > 0 {
+            assert!(tm.step_back());
+            assert_eq!(snapshot(&tm), snapshots[n as usize - 1]);
+        }
+    }
+}
@@ -0,0 +1,185 @@
+extern crate turing_machines;
+
+use turing_machines::{TMDesc, TM, StepOutcome, HaltKind, Format};
+
+/// Accepts a^n b^n, as drawn in JFLAP 7.
+static ANBN: &'static str = r#"<?xml version="1.0" encoding="UTF-8" standalone="no"?><!--Created with JFLAP 7.1.--><structure>
+	<type>turing</type>
+	<automaton>
+		<!--The list of states.-->
+		<state id="0" name="q0">
+			<x>84.0</x>
+			<y>123.0</y>
+			<initial/>
+		</state>
+		<state id="1" name="q1">
+			<x>237.0</x>
+			<y>123.0</y>
+		</state>
+		<state id="2" name="q2">
+			<x>390.0</x>
+			<y>123.0</y>
+		</state>
+		<state id="3" name="q3">
+			<x>84.0</x>
+			<y>276.0</y>
+			<final/>
+		</state>
+		<!--The list of transitions.-->
+		<transition>
+			<from>0</from>
+			<to>1</to>
+			<read>a</read>
+			<write/>
+			<move>R</move>
+		</transition>
+		<transition>
+			<from>0</from>
+			<to>3</to>
+			<read/>
+			<write/>
+			<move>S</move>
+		</transition>
+		<transition>
+			<from>1</from>
+			<to>1</to>
+			<read>a</read>
+			<write>a</write>
+			<move>R</move>
+		</transition>
+		<transition>
+			<from>1</from>
+			<to>1</to>
+			<read>b</read>
+			<write>b</write>
+			<move>R</move>
+		</transition>
+		<transition>
+			<from>1</from>
+			<to>2</to>
+			<read/>
+			<write/>
+			<move>L</move>
+		</transition>
+		<transition>
+			<from>2</from>
+			<to>4</to>
+			<read>b</read>
+			<write/>
+			<move>L</move>
+		</transition>
+		<transition>
+			<from>4</from>
+			<to>4</to>
+			<read>a</read>
+			<write>a</write>
+			<move>L</move>
+		</transition>
+		<transition>
+			<from>4</from>
+			<to>4</to>
+			<read>b</read>
+			<write>b</write>
+			<move>L</move>
+		</transition>
+		<transition>
+			<from>4</from>
+			<to>0</to>
+			<read/>
+			<write/>
+			<move>R</move>
+		</transition>
+	</automaton>
+</structure>"#;
+
+fn with_state_4(jff: &str) -> String {
+    jff.replace("\t\t<!--The list of transitions.-->",
+                "\t\t<state id=\"4\" name=\"back\"><x>0</x><y>0</y></state>\n\
+                 \t\t<!--The list of transitions.-->")
+}
+
+fn run(desc: &TMDesc, input: &str) -> StepOutcome {
+    let mut tm = TM::new(desc, input).unwrap();
+    loop {
+        match tm.run_step().unwrap() {
+            StepOutcome::Continue => (),
+            outcome => return outcome
+        }
+    }
+}
+
+#[test]
+fn test_anbn() {
+    let desc = TMDesc::from_jflap(&with_state_4(ANBN)).unwrap();
+    assert_eq!(desc.input_symbols, vec!['a', 'b', '□']);
+    assert_eq!(desc.states.iter().map(|s| &s.name[..]).collect::<Vec<_>>(),
+               vec!["q0", "q1", "q2", "q3", "back", "reject"]);
+    assert_eq!(desc.states[3].halt, Some(HaltKind::Accept));
+    assert_eq!(run(&desc, "aabb"), StepOutcome::Halted(HaltKind::Accept));
+    assert_eq!(run(&desc, ""), StepOutcome::Halted(HaltKind::Accept));
+    assert_eq!(run(&desc, "aab"), StepOutcome::Halted(HaltKind::Reject));
+    assert_eq!(run(&desc, "ba"), StepOutcome::Halted(HaltKind::Reject));
+    let reject = desc.find_state("reject").unwrap();
+    assert_eq!(desc.states[reject].halt, Some(HaltKind::Reject));
+    match desc.states[0].transitions[1] {
+        Some(ref trans) => assert_eq!((&trans.state[..], trans.symbol), ("reject", 'b')),
+        None => panic!("q0 has no transition for `b'")
+    }
+}
+
+#[test]
+fn test_round_trip() {
+    let desc = TMDesc::from_string_in(Format::Jflap, &with_state_4(ANBN)).unwrap();
+    let jff = desc.to_jflap().unwrap();
+    assert!(jff.contains("\t\t<state id=\"0\" name=\"q0\">\n\t\t\t<x>100.0</x>\n\
+                          \t\t\t<y>100.0</y>\n\t\t\t<initial/>\n\t\t</state>\n"));
+    assert!(jff.contains("\t\t<transition>\n\t\t\t<from>0</from>\n\t\t\t<to>3</to>\n\
+                          \t\t\t<read/>\n\t\t\t<write/>\n\t\t\t<move>S</move>\n\
+                          \t\t</transition>\n"));
+    assert!(!jff.contains("<to>5</to>"));
+    let read = TMDesc::from_jflap(&jff).unwrap();
+    assert_eq!(read.to_string(), desc.to_string());
+
+    // Names are escaped, and the blank is written as an empty element.
+    let desc = TMDesc::from_string("
+	<	B
+a&b	STOPP,B,R	-
+STOPP
+").unwrap();
+    let jff = desc.to_jflap().unwrap();
+    assert!(jff.contains("<state id=\"0\" name=\"a&amp;b\">"));
+    assert!(jff.contains("<read>&lt;</read>\n\t\t\t<write/>"));
+    assert!(jff.contains("<state id=\"1\" name=\"STOPP\">\n\t\t\t<x>250.0</x>\n\
+                          \t\t\t<y>100.0</y>\n\t\t\t<final/>\n"));
+    let read = TMDesc::from_jflap(&jff).unwrap();
+    assert_eq!(read.input_symbols, vec!['<', '□']);
+    assert_eq!(read.states[0].name, "a&b");
+
+    // Rejecting states are kept.
+    let desc = TMDesc::from_string(include_str!("../data/parity.tm")).unwrap();
+    let read = TMDesc::from_jflap(&desc.to_jflap().unwrap()).unwrap();
+    assert_eq!(run(&read, "11"), StepOutcome::Halted(HaltKind::Accept));
+    assert_eq!(run(&read, "111"), StepOutcome::Halted(HaltKind::Reject));
+    assert_eq!(read.to_string().replace("□", "B"), desc.to_string());
+
+    // Machines with undefined states can't be written.
+    let mut desc = desc;
+    desc.states[0].transitions[0].as_mut().unwrap().state = "nowhere".into();
+    assert_eq!(desc.to_jflap().unwrap_err().to_string(),
+               "the machine can't be written in JFLAP format: state `nowhere' is not defined");
+}
+
+fn error(jff: &str) -> String {
+    TMDesc::from_jflap(jff).unwrap_err().to_string()
+}
+
+#[test]
+fn test_errors() {
+    assert_eq!(error(ANBN), "59:3: there is no state 4 (in JFLAP format)");
+    assert_eq!(error("<structure><type>fa</type></structure>"),
+               "1:1: the automaton is of type `fa', not a Turing machine (in JFLAP format)");
+    assert_eq!(error("<structure>\n<type>turing</tpye></structure>"),
+               "2:15: expected `</type>' (in JFLAP format)");
+    assert_eq!(error("<structure><type>turing</type><state id=\"0\"/></structure>"),
+               "there is no initial state (in JFLAP format)");
+}
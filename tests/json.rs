@@ -0,0 +1,68 @@
+extern crate turing_machines;
+extern crate rustc_serialize;
+
+use turing_machines::{TMDesc, TMDescError, HaltKind, Format};
+use rustc_serialize::json::ToJson;
+
+static PARITY_TM: &'static str = include_str!("../data/parity.tm");
+static HELLO_TM: &'static str = include_str!("../data/hello.tm");
+
+static PARITY_JSON: &'static str = r#"{
+  "alphabet": ["B", "1"],
+  "blank": "B",
+  "initial": "even",
+  "halting": {"accept": ["ACCEPT"], "reject": ["REJECT"]},
+  "states": [
+    {"name": "odd", "transitions": {
+      "1": {"next": "even", "write": "1", "move": "R"},
+      "B": {"next": "REJECT", "write": "B", "move": "N"}
+    }},
+    {"name": "even", "transitions": {
+      "1": {"next": "odd", "write": "1", "move": "R"},
+      "B": {"next": "ACCEPT", "write": "B", "move": "N"}
+    }}
+  ]
+}"#;
+
+#[test]
+fn test_round_trip() {
+    for source in [PARITY_TM, HELLO_TM].iter() {
+        let desc = TMDesc::from_string(source).unwrap();
+        let json = desc.to_string_in(Format::Json).unwrap();
+        let read = TMDesc::from_string_in(Format::Json, &json).unwrap();
+        assert_eq!(read.to_string(), desc.to_string());
+        assert_eq!(read.to_json(), desc.to_json());
+    }
+}
+
+#[test]
+fn test_import() {
+    let desc = TMDesc::from_json_str(PARITY_JSON).unwrap();
+    let parity = TMDesc::from_string(PARITY_TM).unwrap();
+    assert_eq!(desc.input_symbols, vec!['1', 'B']);
+    assert_eq!(desc.states[0].name, "even");
+    assert_eq!(desc.states[2].halt, Some(HaltKind::Accept));
+    assert_eq!(desc.to_string(), parity.to_string());
+}
+
+fn message(json: &str) -> String {
+    match TMDesc::from_json_str(json) {
+        Err(TMDescError::Malformed { format: Format::Json, line, column, message }) =>
+            format!("{}:{}: {}", line, column, message),
+        other => panic!("unexpected result {:?}", other)
+    }
+}
+
+#[test]
+fn test_errors() {
+    assert_eq!(message("{\"alphabet\": [\"0\"]"), "1:19: EOF While parsing object");
+    assert_eq!(message("{\"states\": []}"), "0:0: .alphabet is missing");
+    assert_eq!(message(r#"{"alphabet": ["0", "B"], "states": [
+        {"name": "q0", "transitions": {"0": {"next": "q0", "write": "0", "move": "X"}}}
+    ]}"#), "0:0: .states[0].transitions.0.move `X' is not a valid movement (L, R or N)");
+    assert_eq!(message(r#"{"alphabet": ["0"], "states": [
+        {"name": "q0", "transitions": {"0": {"next": "q1", "write": "0", "move": "L"}}}
+    ]}"#), "0:0: state `q1' is not defined");
+    assert_eq!(message(r#"{"alphabet": ["0"], "blank": "B", "states": []}"#),
+               "0:0: the blank `B' is not in .alphabet");
+}
@@ -0,0 +1,90 @@
+extern crate turing_machines;
+
+use turing_machines::{TMDesc, TMDescError, Format};
+
+static HELLO_TM: &'static str = include_str!("../data/hello.tm");
+
+fn entries(desc: &TMDesc, state: &str) -> Vec<String> {
+    let state = &desc.states[desc.find_state(state).unwrap()];
+    state.transitions.iter().map(|t| match *t {
+        Some(ref t) => format!("{},{},{}", t.state, t.symbol, t.movement.to_char()),
+        None => "-".into()
+    }).collect()
+}
+
+#[test]
+fn test_hello() {
+    let desc = TMDesc::from_string(HELLO_TM).unwrap();
+    assert_eq!(entries(&desc, "q←"),
+               vec!["q←,H,L", "q←,E,L", "q←,L,L", "q←,O,L", "q←,.,L", "q←,W,L",
+                    "q←,R,L", "q←,D,L", "q←,!,L", "STOPP,B,R"]);
+}
+
+#[test]
+fn test_keyed_rows() {
+    let desc = TMDesc::from_string("
+	0	1	:	B
+q0	1:q1,0,R	*:=,=,R
+q1	B:STOPP,=,N	::-	0:q0,1,L
+q2	=,=,L	-	q0,=,R	-
+STOPP
+").unwrap();
+    assert_eq!(entries(&desc, "q0"), vec!["q0,0,R", "q1,0,R", "q0,:,R", "q0,B,R"]);
+    assert_eq!(entries(&desc, "q1"), vec!["q0,1,L", "-", "-", "STOPP,B,N"]);
+    assert_eq!(entries(&desc, "q2"), vec!["q2,0,L", "-", "q0,:,R", "-"]);
+
+    // A symbol called `*' only gets the default.
+    let star = TMDesc::from_string("\t*\tB\nq0\tB:=,*,R\t*:STOPP,B,N\nSTOPP\n").unwrap();
+    assert_eq!(entries(&star, "q0"), vec!["STOPP,B,N", "q0,*,R"]);
+
+    // The writer spells everything out.
+    let written = TMDesc::from_string(&desc.to_string()).unwrap();
+    assert_eq!(written.to_string(), desc.to_string());
+}
+
+#[test]
+fn test_keyed_errors() {
+    let error = |row: &str| {
+        TMDesc::from_string(&format!("\tA\tB\nq0\t{}\n", row)).unwrap_err()
+    };
+    match error("A:q0,A,R\tC:q0,A,R") {
+        TMDescError::UnknownSymbol { line, column, text } => {
+            assert_eq!((line, column, &text[..]), (2, 13, "C"));
+        }
+        other => panic!("unexpected result: {:?}", other)
+    }
+    match error("*:q0,A,R\tA:q0,A,R\t*:-") {
+        TMDescError::DuplicateEntry { line, column, text } => {
+            assert_eq!((line, column, &text[..]), (2, 22, "*"));
+        }
+        other => panic!("unexpected result: {:?}", other)
+    }
+    match error("A:q0,A,X\t*:-") {
+        TMDescError::InvalidMovement { line, column, .. } => assert_eq!((line, column), (2, 11)),
+        other => panic!("unexpected result: {:?}", other)
+    }
+    match error("*:q9,=,L") {
+        TMDescError::UnknownState { line, column, text } => {
+            assert_eq!((line, column, &text[..]), (2, 6, "q9"));
+        }
+        other => panic!("unexpected result: {:?}", other)
+    }
+}
+
+#[test]
+fn test_reserved_names() {
+    let error = |source: &str| match TMDesc::from_string(source) {
+        Err(TMDescError::ReservedName { line, column, text }) => (line, column, text),
+        other => panic!("unexpected result: {:?}", other)
+    };
+    assert_eq!(error("\t1\t+\t=\tB\nq0\tq0,=,R\t-\t-\t-\n"), (1, 6, "=".into()));
+    assert_eq!(error("\t1\tB\n=\t=,1,R\t-\n"), (2, 1, "=".into()));
+    assert_eq!(error("\t1\tB\nq0\tq0,1,R\t-\n!halt\tq0\t=\n"), (3, 10, "=".into()));
+
+    // Such machines can't be written either.
+    let mut desc = TMDesc::from_string("\t1\tB\nq0\tq0,1,R\t-\n").unwrap();
+    assert!(desc.to_string_in(Format::Tm).is_ok());
+    desc.input_symbols[0] = '=';
+    assert_eq!(desc.to_string_in(Format::Tm).unwrap_err().to_string(),
+               "the machine can't be written in TM format: `=' can't be a symbol or state name");
+}
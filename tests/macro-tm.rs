@@ -0,0 +1,82 @@
+extern crate turing_machines;
+
+use turing_machines::{TMDesc, FastTM, MacroTM, StepOutcome, HaltKind, RunErrorKind};
+
+static HELLO_TM: &'static str = include_str!("../data/hello.tm");
+static PARITY_TM: &'static str = include_str!("../data/parity.tm");
+
+// Writes 1s to the right forever.
+static SWEEP_TM: &'static str = "
+	1	B
+q0	q0,1,R	q0,1,R
+";
+
+// Goes back and forth between two cells forever.
+static PING_PONG_TM: &'static str = "
+	1	B
+q0	q1,1,R	q1,1,R
+q1	q0,1,L	q0,1,L
+";
+
+// Fills a long input with 0s from the right, then halts.
+static ERASE_TM: &'static str = "
+	0	1	B
+right	right,0,R	right,1,R	left,B,L
+left	left,0,L	left,0,L	STOPP,B,R
+STOPP
+";
+
+#[test]
+fn test_same_as_fast_tm() {
+    let input = (0..1000).map(|_| '1').collect::<String>();
+    let machines = [(HELLO_TM, ""), (PARITY_TM, "1111"), (PARITY_TM, "111"),
+                    (ERASE_TM, &input[..])];
+    for &(machine, input) in machines.iter() {
+        let desc = TMDesc::from_string(machine).unwrap();
+        let mut fast = FastTM::new(&desc, input).unwrap();
+        let outcome = fast.run(None).unwrap();
+
+        for block_size in 1..4 {
+            let mut tm = MacroTM::new(&desc, input, block_size).unwrap();
+            assert_eq!(tm.run(None).unwrap(), outcome);
+            assert_eq!(tm.steps(), fast.steps());
+            assert_eq!(tm.head(), fast.head());
+            assert_eq!(tm.state().name, fast.state().name);
+            assert_eq!(tm.tape().to_string(), fast.tape().to_string());
+            assert_eq!(tm.tape().min(), fast.tape().min());
+        }
+    }
+}
+
+#[test]
+fn test_long_sweeps() {
+    let input = (0..1000000).map(|_| '1').collect::<String>();
+    let desc = TMDesc::from_string(ERASE_TM).unwrap();
+    let mut tm = MacroTM::new(&desc, &input, 1).unwrap();
+    assert_eq!(tm.run(None).unwrap(), StepOutcome::Halted(HaltKind::Halt));
+    assert_eq!(tm.steps(), 2000002);
+    assert_eq!(tm.head(), 0);
+}
+
+#[test]
+fn test_never_halts() {
+    let desc = TMDesc::from_string(SWEEP_TM).unwrap();
+    let mut tm = MacroTM::new(&desc, "", 2).unwrap();
+    assert_eq!(tm.run(None).unwrap(), StepOutcome::Continue);
+    assert!(tm.never_halts());
+
+    let desc = TMDesc::from_string(PING_PONG_TM).unwrap();
+    let mut tm = MacroTM::new(&desc, "", 2).unwrap();
+    assert_eq!(tm.run(Some(1000)).unwrap(), StepOutcome::Continue);
+    assert!(tm.never_halts());
+    assert_eq!(tm.steps(), 0);
+}
+
+#[test]
+fn test_unknown_symbol() {
+    let desc = TMDesc::from_string(PARITY_TM).unwrap();
+    let mut tm = MacroTM::new(&desc, "11x", 2).unwrap();
+    let err = tm.run(None).unwrap_err();
+    assert_eq!(err.kind, RunErrorKind::UnknownSymbol);
+    assert_eq!((err.head, err.symbol, err.step), (2, 'x', 2));
+}
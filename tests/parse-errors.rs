@@ -0,0 +1,50 @@
+extern crate turing_machines;
+
+use turing_machines::{TMDesc, TMDescError};
+
+#[test]
+fn test_invalid_movement() {
+    let tm = "
+# a comment
+	A	B
+q0	q0,A,R	q1,B,X
+q1
+";
+    match TMDesc::from_string(tm) {
+        Err(TMDescError::InvalidMovement { line, column, text }) => {
+            assert_eq!((line, column), (4, 16));
+            assert_eq!(text, "X");
+        }
+        other => panic!("unexpected result: {:?}", other)
+    }
+}
+
+#[test]
+fn test_unknown_state() {
+    let tm = "
+	A	B
+q0	q0,A,R	q9,B,R
+q1
+";
+    match TMDesc::from_string(tm) {
+        Err(TMDescError::UnknownState { line, column, text }) => {
+            assert_eq!((line, column), (3, 11));
+            assert_eq!(text, "q9");
+        }
+        other => panic!("unexpected result: {:?}", other)
+    }
+}
+
+#[test]
+fn test_row_length() {
+    let tm = "
+	A	B
+q0	q0,A,R
+";
+    match TMDesc::from_string(tm) {
+        Err(TMDescError::RowLength { line, expected, found, .. }) => {
+            assert_eq!((line, expected, found), (3, 2, 1));
+        }
+        other => panic!("unexpected result: {:?}", other)
+    }
+}
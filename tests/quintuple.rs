@@ -0,0 +1,148 @@
+extern crate turing_machines;
+
+use turing_machines::{TMDesc, TM, StepOutcome, HaltKind, Format};
+
+/// Checks for palindromes over 0 and 1.
+static PALINDROME: &'static str = "\
+; Machine starts in state 0.
+
+; State 0: read the leftmost symbol
+0 0 _ r 1o
+0 1 _ r 1i
+0 _ _ * accept     ; Empty input
+
+; State 1o, 1i: find the rightmost symbol
+1o _ _ l 2o
+1o * * r 1o
+1i _ _ l 2i
+1i * * r 1i
+
+; State 2o, 2i: check that the rightmost symbol matches
+2o 0 _ l 3
+2o _ _ * accept
+2o * * * reject
+2i 1 _ l 3
+2i _ _ * accept
+2i * * * reject
+
+; State 3, 4: return to the left end and start again
+3 _ _ * accept
+3 * * l 4
+4 * * l 4
+4 _ _ r 0 !
+
+accept * : r accept2
+accept2 * ) * halt-accept
+reject _ : r reject2
+reject * _ l reject
+reject2 * ( * halt-reject
+";
+
+fn run(desc: &TMDesc, input: &str) -> StepOutcome {
+    let mut tm = TM::new(desc, input).unwrap();
+    loop {
+        match tm.run_step().unwrap() {
+            StepOutcome::Continue => (),
+            outcome => return outcome
+        }
+    }
+}
+
+#[test]
+fn test_palindrome() {
+    let desc = TMDesc::from_quintuples(PALINDROME).unwrap();
+    assert_eq!(desc.input_symbols, vec!['0', '1', ':', ')', '(', '_']);
+    assert_eq!(desc.states[0].name, "0");
+    assert_eq!(desc.find_state("halt-accept").map(|i| desc.states[i].halt),
+               Some(Some(HaltKind::Accept)));
+    assert_eq!(run(&desc, "0110"), StepOutcome::Halted(HaltKind::Accept));
+    assert_eq!(run(&desc, "10101"), StepOutcome::Halted(HaltKind::Accept));
+    assert_eq!(run(&desc, "100"), StepOutcome::Halted(HaltKind::Reject));
+    assert_eq!(run(&desc, ""), StepOutcome::Halted(HaltKind::Accept));
+}
+
+#[test]
+fn test_precedence() {
+    let error = TMDesc::from_string_in(Format::Quintuple, "
+* * * r any
+q a b l halt
+any
+").unwrap_err();
+    assert_eq!(error.to_string(), "4:1: a rule needs 5 parts, not 1 (in quintuple format)");
+
+    let desc = TMDesc::from_quintuples("
+q a b l halt
+* * * r any
+* a x r any-a
+q * * l q-any
+q b * * *
+").unwrap();
+    assert_eq!(desc.input_symbols, vec!['a', 'b', 'x', '_']);
+    let q = &desc.states[0];
+    assert_eq!(q.name, "q");
+    let targets = q.transitions.iter().map(|t| {
+        let t = t.as_ref().unwrap();
+        format!("{},{},{}", t.state, t.symbol, t.movement.to_char())
+    }).collect::<Vec<_>>();
+    assert_eq!(targets, vec!["halt,b,L", "q,b,N", "q-any,x,L", "q-any,_,L"]);
+    let any = &desc.states[desc.find_state("any").unwrap()];
+    assert_eq!(any.transitions[0].as_ref().unwrap().state, "any-a");
+    assert_eq!(any.transitions[1].as_ref().unwrap().state, "any");
+}
+
+#[test]
+fn test_round_trip() {
+    let desc = TMDesc::from_quintuples(PALINDROME).unwrap();
+    let rules = desc.to_string_in(Format::Quintuple).unwrap();
+    assert!(rules.starts_with("0 0 _ r 1o\n0 1 _ r 1i\n"));
+    // The states come in a different order.
+    let read = TMDesc::from_quintuples(&rules).unwrap();
+    let sorted = |desc: &TMDesc| {
+        let text = desc.to_string();
+        let mut lines = text.lines().map(|l| l.to_string()).collect::<Vec<_>>();
+        lines.sort();
+        lines
+    };
+    assert_eq!(sorted(&read), sorted(&desc));
+
+    let parity = TMDesc::from_string(include_str!("../data/parity.tm")).unwrap();
+    let rules = parity.to_quintuples().unwrap();
+    assert_eq!(rules, "\
+even 1 1 r odd
+even _ _ * halt-accept
+odd 1 1 r even
+odd _ _ * halt-reject
+");
+    let read = TMDesc::from_quintuples(&rules).unwrap();
+    assert_eq!(read.states[0].name, "even");
+    assert_eq!(run(&read, "1111"), StepOutcome::Halted(HaltKind::Accept));
+}
+
+#[test]
+fn test_detected() {
+    // Rules are read as such where a TM file is expected.
+    let desc = TMDesc::from_string(PALINDROME).unwrap();
+    assert_eq!(desc.to_string(), TMDesc::from_quintuples(PALINDROME).unwrap().to_string());
+    assert_eq!(run(&desc, "0110"), StepOutcome::Halted(HaltKind::Accept));
+    let desc = TMDesc::from_string_in(Format::Tm, "; comment\n\n0 _ 1 * halt\n").unwrap();
+    assert_eq!(desc.states[0].name, "0");
+
+    // TM files are still TM files, even with five symbols.
+    let desc = TMDesc::from_string("# a b c d\n\ta\tb\tc\td\nq0\t-\t-\t-\t-\n").unwrap();
+    assert_eq!(desc.input_symbols, vec!['a', 'b', 'c', 'd']);
+    // The first cell of the header is ignored, so it needn't be empty.
+    let desc = TMDesc::from_string("x\ta\tb\tl\tc\nq0\t-\t-\t-\t-\n").unwrap();
+    assert_eq!(desc.input_symbols, vec!['a', 'b', 'l', 'c']);
+}
+
+#[test]
+fn test_errors() {
+    let error = |s: &str| TMDesc::from_quintuples(s).unwrap_err().to_string();
+    assert_eq!(error("0 a b x 1"),
+               "1:7: `x' is not a valid direction (l, r or *) (in quintuple format)");
+    assert_eq!(error("\n0 ab b l 1"),
+               "2:3: symbol `ab' is not a single character (in quintuple format)");
+    assert_eq!(error("0 a b l 1\n  0 a c r 2"),
+               "2:3: this rule is already defined on line 1 (in quintuple format)");
+    assert_eq!(error("; nothing\n"), "there are no rules (in quintuple format)");
+}
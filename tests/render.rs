@@ -0,0 +1,26 @@
+extern crate turing_machines;
+
+use turing_machines::{TMDesc, TM, Tape, RenderStyle, render};
+
+static HELLO_TM: &'static str = include_str!("../data/hello.tm");
+
+#[test]
+fn test_render() {
+    let tape = Tape::from_str("B1101B", 'B');
+    assert_eq!(render(&tape, 2, "q3", None, RenderStyle::Plain), "B1[q3]101B");
+    assert_eq!(render(&tape, 2, "q3", Some(1), RenderStyle::Plain), "…1[q3]10…");
+    assert_eq!(render(&tape, 8, "q3", Some(1), RenderStyle::Plain), "…B[q3]BB");
+    assert_eq!(render(&tape, 0, "q0", Some(0), RenderStyle::Ansi),
+               "\x1b[1;36m[q0]\x1b[0m\x1b[7mB\x1b[0m…");
+}
+
+#[test]
+fn test_display() {
+    let desc = TMDesc::from_string(HELLO_TM).unwrap();
+    let mut tm = TM::new(&desc, "").unwrap();
+    assert_eq!(tm.to_string(), "BBBBBBBBBB[q0]BBBBBBBBBBB");
+    for _ in 0..3 {
+        tm.run_step().unwrap();
+    }
+    assert_eq!(tm.render(None, RenderStyle::Plain), "HEL[q3]B");
+}
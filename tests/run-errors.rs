@@ -0,0 +1,65 @@
+extern crate turing_machines;
+
+use turing_machines::{TMDesc, TM, StepOutcome, HaltKind, RunErrorKind, Diagnostic};
+use turing_machines::{Configuration, ConfigurationError};
+
+static TM: &'static str = "
+	A	B
+q0	q0,A,R	-
+STOPP
+";
+
+#[test]
+fn test_stuck() {
+    let desc = TMDesc::from_string(TM).unwrap();
+    let mut tm = TM::new(&desc, "AA").unwrap();
+
+    assert_eq!(tm.run_step().unwrap(), StepOutcome::Continue);
+    assert_eq!(tm.run_step().unwrap(), StepOutcome::Continue);
+    assert_eq!(tm.run_step().unwrap(), StepOutcome::Stuck);
+    assert_eq!(tm.run_step().unwrap(), StepOutcome::Stuck);
+    assert_eq!(tm.steps(), 2);
+}
+
+#[test]
+fn test_unknown_symbol() {
+    let desc = TMDesc::from_string(TM).unwrap();
+    let mut tm = TM::new(&desc, "AX").unwrap();
+
+    tm.run_step().unwrap();
+    let err = tm.run_step().unwrap_err();
+    assert_eq!(err.kind, RunErrorKind::UnknownSymbol);
+    assert_eq!((&err.state[..], err.head, err.symbol, err.step), ("q0", 1, 'X', 1));
+}
+
+#[test]
+fn test_custom_blank() {
+    let desc = TMDesc::from_string("
+	1	_
+q0	q0,1,R	STOPP,1,L
+STOPP
+").unwrap();
+    let mut tm = TM::new(&desc, "11").unwrap();
+
+    for _ in 0..3 {
+        tm.run_step().unwrap();
+    }
+    assert_eq!(tm.run_step().unwrap(), StepOutcome::Halted(HaltKind::Halt));
+    assert_eq!(tm.get_tape_output(), "11");
+}
+
+#[test]
+fn test_empty() {
+    // Machines without symbols or states can't even start.
+    assert_eq!(TM::new(&TMDesc::new(), "").err(), Some(Diagnostic::NoSymbols));
+    let desc = TMDesc::from_string("\tA\tB\n").unwrap();
+    assert_eq!(TM::new(&desc, "A").err(), Some(Diagnostic::NoStates));
+
+    let configuration = Configuration::from_string(
+        r#"{"state": "q0", "head": 0, "tape": "", "tape_start": 0, "steps": 0}"#).unwrap();
+    match TM::from_configuration(&TMDesc::new(), &configuration) {
+        Err(ConfigurationError::Invalid(Diagnostic::NoSymbols)) => (),
+        Err(e) => panic!("unexpected error {}", e),
+        Ok(_) => panic!("empty machine resumed")
+    }
+}
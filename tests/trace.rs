@@ -0,0 +1,62 @@
+extern crate turing_machines;
+extern crate rustc_serialize;
+
+use turing_machines::{TMDesc, TM, StepOutcome, Tracer, TraceFormat};
+use rustc_serialize::json::Json;
+
+static PARITY_TM: &'static str = include_str!("../data/parity.tm");
+
+/// Run the parity machine to the end and return the lines of its trace.
+fn trace(input: &str, format: TraceFormat, window: usize) -> Vec<String> {
+    let desc = TMDesc::from_string(PARITY_TM).unwrap();
+    let mut tm = TM::new(&desc, input).unwrap();
+    let mut tracer = Tracer::new(vec![], format, window);
+    loop {
+        let outcome = tm.run_step().unwrap();
+        tracer.trace(&tm).unwrap();
+        if outcome != StepOutcome::Continue {
+            break;
+        }
+    }
+    let out = String::from_utf8(tracer.into_inner()).unwrap();
+    out.lines().map(|l| l.to_string()).collect()
+}
+
+#[test]
+fn test_human() {
+    assert_eq!(trace("11", TraceFormat::Human, 1), vec![
+        "       1  even @ 0: '1' -> '1' R odd  1[odd]1B",
+        "       2  odd @ 1: '1' -> '1' R even  …1[even]BB",
+        "       3  even @ 2: 'B' -> 'B' N ACCEPT  …1[ACCEPT]BB"
+    ]);
+    assert_eq!(trace("1", TraceFormat::Human, 0), vec![
+        "       1  even @ 0: '1' -> '1' R odd  …[odd]B",
+        "       2  odd @ 1: 'B' -> 'B' N REJECT  …[REJECT]B"
+    ]);
+}
+
+#[test]
+fn test_json() {
+    let steps = trace("11", TraceFormat::Json, 1).iter().map(|line| {
+        let json = Json::from_str(line).unwrap();
+        let field = |name: &str| match json.find(name).unwrap() {
+            &Json::String(ref s) => s.clone(),
+            other => other.to_string()
+        };
+        ["step", "state", "head", "read", "write", "move", "next", "window", "window_start"]
+            .iter().map(|name| field(name)).collect::<Vec<_>>().join(" ")
+    }).collect::<Vec<_>>();
+    // The window is around the new head, like in human-readable traces, and
+    // reaches past the end of the used tape, which reads as blanks.
+    assert_eq!(steps, vec![
+        "1 even 0 1 1 R odd 11B 0",
+        "2 odd 1 1 1 R even 1BB 1",
+        "3 even 2 B B N ACCEPT 1BB 1"
+    ]);
+
+    let lines = trace("", TraceFormat::Json, 2);
+    assert_eq!(lines, vec![
+        "{\"head\":0,\"move\":\"N\",\"next\":\"ACCEPT\",\"read\":\"B\",\"state\":\"even\",\
+         \"step\":1,\"window\":\"BBBBB\",\"window_start\":-2,\"write\":\"B\"}"
+    ]);
+}
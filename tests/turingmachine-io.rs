@@ -0,0 +1,125 @@
+extern crate turing_machines;
+
+use turing_machines::{TMDesc, TM, StepOutcome, HaltKind, Format};
+
+static INCREMENT: &'static str = "\
+# Adds 1 to a binary number.
+input: '1011'
+blank: ' '
+start state: right
+table:
+  # scan to the rightmost digit
+  right:
+    [1,0]: R
+    ' '  : {L: carry}
+  # then carry the 1
+  carry:
+    1      : {write: 0, L}
+    [0,' ']: {write: 1, L: done}
+  done:
+";
+
+fn run(desc: &TMDesc, input: &str) -> (StepOutcome, String) {
+    let mut tm = TM::new(desc, input).unwrap();
+    loop {
+        match tm.run_step().unwrap() {
+            StepOutcome::Continue => (),
+            outcome => return (outcome, tm.tape().to_string().trim().into())
+        }
+    }
+}
+
+#[test]
+fn test_increment() {
+    let desc = TMDesc::from_yaml(INCREMENT).unwrap();
+    assert_eq!(desc.input_symbols, vec!['1', '0', ' ']);
+    assert_eq!(desc.states.iter().map(|s| &s.name[..]).collect::<Vec<_>>(),
+               vec!["right", "carry", "done"]);
+    assert_eq!(desc.states[2].halt, Some(HaltKind::Halt));
+    assert_eq!(run(&desc, "1011"), (StepOutcome::Halted(HaltKind::Halt), "1100".into()));
+    assert_eq!(run(&desc, "111"), (StepOutcome::Halted(HaltKind::Halt), "1000".into()));
+}
+
+#[test]
+fn test_round_trip() {
+    let desc = TMDesc::from_string_in(Format::Yaml, INCREMENT).unwrap();
+    let yaml = desc.to_yaml().unwrap();
+    assert_eq!(yaml, "\
+blank: ' '
+start state: right
+table:
+  right:
+    [1, 0]: R
+    ' ': {L: carry}
+  carry:
+    1: {write: 0, L}
+    [0, ' ']: {write: 1, L: done}
+  done:
+");
+    let read = TMDesc::from_yaml(&yaml).unwrap();
+    assert_eq!(read.to_string(), desc.to_string());
+
+    let parity = TMDesc::from_string(include_str!("../data/parity.tm")).unwrap();
+    assert_eq!(parity.to_yaml().unwrap_err().to_string(),
+               "the machine can't be written in turingmachine.io YAML format: \
+                state even doesn't move on `B'");
+}
+
+#[test]
+fn test_block_instructions() {
+    let desc = TMDesc::from_yaml("
+blank: 0
+start state: a
+table:
+  a:
+    0:
+      write: 1
+      R: 'b'
+  \"b\": {}
+").unwrap();
+    assert_eq!(desc.input_symbols, vec!['1', '0']);
+    assert_eq!(run(&desc, ""), (StepOutcome::Halted(HaltKind::Halt), "10".into()));
+}
+
+#[test]
+fn test_missing_instructions() {
+    // The simulator halts when there is no instruction.
+    let yaml = "blank: ' '\nstart state: s\ntable:\n  s: {1: R}\n";
+    let desc = TMDesc::from_yaml(yaml).unwrap();
+    assert_eq!(desc.states.iter().map(|s| &s.name[..]).collect::<Vec<_>>(), vec!["s", "halt"]);
+    assert_eq!(desc.states[1].halt, Some(HaltKind::Halt));
+    assert_eq!(run(&desc, "11"), (StepOutcome::Halted(HaltKind::Halt), "11".into()));
+    let mut tm = TM::new(&desc, "11").unwrap();
+    while tm.run_step().unwrap() == StepOutcome::Continue {}
+    assert_eq!((tm.steps(), tm.head()), (3, 2));
+
+    // The halting transitions are left out again.
+    let written = desc.to_yaml().unwrap();
+    assert_eq!(written, "blank: ' '\nstart state: s\ntable:\n  s:\n    1: R\n  halt:\n");
+    assert_eq!(TMDesc::from_yaml(&written).unwrap().to_string(), desc.to_string());
+
+    // A state called `halt' that doesn't halt is left alone.
+    let yaml = "blank: ' '\nstart state: s\ntable:\n  s: {1: {R: halt}}\n  halt: {1: L}\n";
+    let desc = TMDesc::from_yaml(yaml).unwrap();
+    assert_eq!(desc.states.iter().map(|s| &s.name[..]).collect::<Vec<_>>(),
+               vec!["s", "halt", "halt'"]);
+    assert_eq!(run(&desc, "1"), (StepOutcome::Halted(HaltKind::Halt), "1".into()));
+}
+
+fn error(yaml: &str) -> String {
+    TMDesc::from_yaml(yaml).unwrap_err().to_string()
+}
+
+#[test]
+fn test_errors() {
+    assert_eq!(error("blank: ' '\nstart state: a\ntable:\n  a:\n    x: {write: 1, L: b\n"),
+                     "5:23: expected `,' or `}' (in turingmachine.io YAML format)");
+    assert_eq!(error("blank: ' '\nstart state: a\ntable:\n  a:\n    x: {write: 1}\n"),
+                     "5:5: the instruction needs L or R (in turingmachine.io YAML format)");
+    assert_eq!(error("blank: ' '\nstart state: a\ntable:\n  a:\n    x: {R: b}\n"),
+                     "5:5: state `b' is not defined (in turingmachine.io YAML format)");
+    assert_eq!(error("start state: a\ntable:\n"),
+                     "`blank' is missing (in turingmachine.io YAML format)");
+    assert_eq!(error("blank: ' '\nstart state: a\ntable:\n  a:\n   - x\n"),
+                     "5:4: sequences are not supported (in turingmachine.io YAML format)");
+}
@@ -0,0 +1,31 @@
+extern crate turing_machines;
+
+use turing_machines::{TMDesc, Diagnostic, Severity};
+
+static HELLO_TM: &'static str = include_str!("../data/hello.tm");
+
+#[test]
+fn test_hello_is_valid() {
+    let desc = TMDesc::from_string(HELLO_TM).unwrap();
+    assert_eq!(desc.validate(), vec![]);
+}
+
+#[test]
+fn test_all_problems_are_reported() {
+    let mut desc = TMDesc::from_string("
+	A	B
+q0	q1,C,R	q0,A,L
+q1	q0,A,R	-
+q2	q0,A,R	q1,B,N
+").unwrap();
+    desc.input_symbols.push('A');
+
+    let diags = desc.validate();
+    assert!(diags.contains(&Diagnostic::DuplicateSymbol('A')));
+    assert!(diags.contains(&Diagnostic::UnknownSymbol {
+        state: "q0".into(), symbol: 'A', written: 'C'
+    }));
+    assert!(diags.contains(&Diagnostic::NoFinalState));
+    assert!(diags.contains(&Diagnostic::Unreachable("q2".into())));
+    assert_eq!(diags.iter().filter(|d| d.severity() == Severity::Warning).count(), 1);
+}
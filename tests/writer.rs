@@ -0,0 +1,64 @@
+extern crate turing_machines;
+
+use turing_machines::{TMDesc, HaltKind};
+
+static PARITY_TM: &'static str = include_str!("../data/parity.tm");
+static HELLO_TM: &'static str = include_str!("../data/hello.tm");
+
+fn round_trip(source: &str) {
+    let desc = TMDesc::from_string(source).unwrap();
+    let written = desc.to_string();
+    let read = TMDesc::from_string(&written).unwrap();
+    assert_eq!(read.input_symbols, desc.input_symbols);
+    assert_eq!(read.states.len(), desc.states.len());
+    for (a, b) in read.states.iter().zip(desc.states.iter()) {
+        assert_eq!(a.name, b.name);
+        assert_eq!(a.halt, b.halt);
+        assert_eq!(format!("{:?}", a.transitions), format!("{:?}", b.transitions));
+    }
+    assert_eq!(read.to_string(), written);
+}
+
+#[test]
+fn test_round_trip() {
+    round_trip(PARITY_TM);
+    round_trip(HELLO_TM);
+}
+
+#[test]
+fn test_aligned() {
+    let desc = TMDesc::from_string("
+	0	1	B
+q0	q0,1,R	-	long,B,L
+long	STOPP,0,N	STOPP,0,N	-
+STOPP
+").unwrap();
+    assert_eq!(desc.to_string(), "     \t0        \t1        \tB
+q0   \tq0,1,R   \t-        \tlong,B,L
+long \tSTOPP,0,N\tSTOPP,0,N\t-
+STOPP
+");
+}
+
+#[test]
+fn test_directives() {
+    let desc = TMDesc::from_string("
+	0	B
+q0	q0,0,R	-
+STOPP
+!halt
+").unwrap();
+    assert!(desc.states.iter().all(|s| s.halt.is_none()));
+    assert!(desc.to_string().ends_with("STOPP\n!halt\n"));
+    round_trip(&desc.to_string());
+
+    let desc = TMDesc::from_string("
+	0	B
+q0	yes,0,R	no,B,N
+!accept	yes
+!reject	no
+").unwrap();
+    assert!(desc.to_string().ends_with("yes\nno\n!accept\tyes\n!reject\tno\n"));
+    assert_eq!(desc.states[1].halt, Some(HaltKind::Accept));
+    round_trip(&desc.to_string());
+}